@@ -1,22 +1,12 @@
-use super::*;
 use blake2b_ref::Blake2bBuilder;
-use ckb_standalone_debugger::transaction::{
-    MockCellDep, MockInfo, MockInput, MockTransaction, ReprMockTransaction,
+use ckb_contract_test_harness::{
+    check_cycles, check_mutants, random_32bytes, verify_tx_all_modes, write_native_setup, Loader,
+    Mutation,
 };
 use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
-use ckb_tool::ckb_types::{
-    bytes::Bytes,
-    core::{DepType, TransactionBuilder, TransactionView},
-    packed::*,
-    prelude::*,
-};
+use ckb_tool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
 use ckb_x64_simulator::RunningSetup;
-use rand::{thread_rng, Rng};
-use serde_json::to_string_pretty;
 use std::collections::HashMap;
-use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
 
 pub fn ckb_hash(data: &[u8]) -> Bytes {
     let mut blake2b = Blake2bBuilder::new(32)
@@ -28,151 +18,17 @@ pub fn ckb_hash(data: &[u8]) -> Bytes {
     Bytes::from(hash)
 }
 
-pub fn random_32bytes() -> Bytes {
-    let mut rng = thread_rng();
-    let mut buf = vec![0u8; 32];
-    rng.fill(&mut buf[..]);
-    Bytes::from(buf)
-}
-
 pub fn amount_to_data(amount: u128) -> Bytes {
     let data = amount.to_le_bytes();
     Bytes::from(data[..].to_vec())
 }
 
-pub fn create_test_folder(name: &str) -> PathBuf {
-    let mut path = TX_FOLDER.clone();
-    path.push(&name);
-    fs::create_dir_all(&path).expect("create folder");
-    path
-}
-
-pub fn build_mock_transaction(tx: &TransactionView, context: &Context) -> MockTransaction {
-    let mock_inputs = tx
-        .inputs()
-        .into_iter()
-        .map(|input| {
-            let (output, data) = context
-                .get_cell(&input.previous_output())
-                .expect("get cell");
-            MockInput {
-                input,
-                output,
-                data,
-                header: None,
-            }
-        })
-        .collect();
-    let mock_cell_deps = tx
-        .cell_deps()
-        .into_iter()
-        .map(|cell_dep| {
-            if cell_dep.dep_type() == DepType::DepGroup.into() {
-                panic!("Implement dep group support later!");
-            }
-            let (output, data) = context.get_cell(&cell_dep.out_point()).expect("get cell");
-            MockCellDep {
-                cell_dep,
-                output,
-                data,
-                header: None,
-            }
-        })
-        .collect();
-    let mock_info = MockInfo {
-        inputs: mock_inputs,
-        cell_deps: mock_cell_deps,
-        header_deps: vec![],
-    };
-    MockTransaction {
-        mock_info,
-        tx: tx.data(),
-    }
-}
-
-pub fn rewrite_setup(setup: &RunningSetup, binary_suffix: &str) -> RunningSetup {
-    let mut setup2 = setup.clone();
-    setup2.native_binaries = setup
-        .native_binaries
-        .iter()
-        .map(|(key, binary)| (key.clone(), format!("{}{}", binary, binary_suffix)))
-        .collect();
-    setup2
-}
-
-pub fn write_native_setup(
-    test_name: &str,
-    binary_name: &str,
-    tx: &TransactionView,
-    context: &Context,
-    setup: &RunningSetup,
-    return_code: i8,
-    enable_sanitizers: bool,
-) {
-    let folder = create_test_folder(test_name);
-    let mock_tx = build_mock_transaction(&tx, &context);
-    let repr_tx: ReprMockTransaction = mock_tx.into();
-    let tx_json = to_string_pretty(&repr_tx).expect("serialize to json");
-    fs::write(folder.join("tx.json"), tx_json).expect("write tx to local file");
-    let setup_json = to_string_pretty(setup).expect("serialize to json");
-    fs::write(folder.join("setup.json"), setup_json).expect("write setup to local file");
-
-    let mut cmd_file = fs::File::create(folder.join("cmd")).expect("create cmd file");
-    write!(
-        &mut cmd_file,
-        "CKB_TX_FILE=\"{}\" CKB_RUNNING_SETUP=\"{}\" \"{}\" 2> err\n",
-        folder.join("tx.json").to_str().expect("utf8"),
-        folder.join("setup.json").to_str().expect("utf8"),
-        Loader::default().path(binary_name).to_str().expect("utf8")
-    )
-    .expect("write");
-    write!(&mut cmd_file, "error_code=$?\nif [ $error_code -ne {} ]; then\n    echo \"Return code $error_code is invalid!\"\n    cat err\n    exit 1\nfi\n", return_code as u8).expect("write");
-
-    if enable_sanitizers {
-        let ubsan_setup = rewrite_setup(setup, ".ubsan");
-        let ubsan_setup_json = to_string_pretty(&ubsan_setup).expect("serialize to json");
-        fs::write(folder.join("ubsan_setup.json"), ubsan_setup_json)
-            .expect("write setup to local file");
-
-        write!(
-            &mut cmd_file,
-            "CKB_TX_FILE=\"{}\" CKB_RUNNING_SETUP=\"{}\" \"{}.ubsan\" 2> err\n",
-            folder.join("tx.json").to_str().expect("utf8"),
-            folder.join("ubsan_setup.json").to_str().expect("utf8"),
-            Loader::default().path(binary_name).to_str().expect("utf8")
-        )
-        .expect("write");
-        write!(&mut cmd_file, "error_code=$?\nif [ $error_code -ne {} ]; then\n    echo \"Return code $error_code is invalid!\"\n    cat err\n    exit 1\nfi\n", return_code as u8).expect("write");
-        write!(
-            &mut cmd_file,
-            "if [ -s err ]; then\n    echo \"Errors in stderr!\"\n    cat err\n    exit 1\nfi\n"
-        )
-        .expect("write");
-
-        let asan_setup = rewrite_setup(setup, ".asan");
-        let asan_setup_json = to_string_pretty(&asan_setup).expect("serialize to json");
-        fs::write(folder.join("asan_setup.json"), asan_setup_json)
-            .expect("write setup to local file");
-
-        write!(
-            &mut cmd_file,
-            "CKB_TX_FILE=\"{}\" CKB_RUNNING_SETUP=\"{}\" \"{}.asan\" 2> err\n",
-            folder.join("tx.json").to_str().expect("utf8"),
-            folder.join("asan_setup.json").to_str().expect("utf8"),
-            Loader::default().path(binary_name).to_str().expect("utf8")
-        )
-        .expect("write");
-        write!(&mut cmd_file, "error_code=$?\nif [ $error_code -ne {} ]; then\n    echo \"Return code $error_code is invalid!\"\n    cat err\n    exit 1\nfi\n", return_code as u8).expect("write");
-        write!(
-            &mut cmd_file,
-            "if [ -s err ]; then\n    echo \"Errors in stderr!\"\n    cat err\n    exit 1\nfi\n"
-        )
-        .expect("write");
-    }
-}
-
 const MAX_CYCLES: u64 = 10_000_000;
 
+// Error codes of simple_udt.c
+const ERROR_ARGUMENTS_LEN: i8 = -1;
+const ERROR_AMOUNT: i8 = -52;
+
 #[test]
 fn test_sudt_transfer() {
     // deploy contract
@@ -241,10 +97,8 @@ fn test_sudt_transfer() {
     let tx = context.complete_tx(tx);
 
     // run
-    let cycles = context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect("pass verification");
-    println!("consume cycles: {}", cycles);
+    let cycles = verify_tx_all_modes(&context, &tx, MAX_CYCLES).expect("pass verification");
+    check_cycles("sudt_transfer", cycles);
 
     // dump raw test tx files
     let setup = RunningSetup {
@@ -262,6 +116,21 @@ fn test_sudt_transfer() {
         0,
         true,
     );
+
+    // reject mutants of the transaction
+    check_mutants(
+        "sudt_transfer",
+        "simple_udt_sim",
+        &tx,
+        &mut context,
+        &setup,
+        MAX_CYCLES,
+        |mutation| match mutation {
+            Mutation::DropInput(_) | Mutation::FlipOutputData(_) => ERROR_AMOUNT,
+            Mutation::TruncateArgs => ERROR_ARGUMENTS_LEN,
+            mutation => panic!("unexpected mutation {}", mutation),
+        },
+    );
 }
 
 #[test]
@@ -332,9 +201,7 @@ fn test_sudt_transfer_failure() {
     let tx = context.complete_tx(tx);
 
     // run
-    context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect_err("fail verification");
+    verify_tx_all_modes(&context, &tx, MAX_CYCLES).expect_err("fail verification");
 
     // dump raw test tx files
     let setup = RunningSetup {
@@ -432,10 +299,8 @@ fn test_dynamic_linking_ok() {
     let tx = context.complete_tx(tx);
 
     // run
-    let cycles = context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect("pass verification");
-    println!("consume cycles: {}", cycles);
+    let cycles = verify_tx_all_modes(&context, &tx, MAX_CYCLES).expect("pass verification");
+    check_cycles("dynamic_linking_ok", cycles);
 
     // dump raw test tx files
     let mut native_binaries = HashMap::default();
@@ -9,12 +9,10 @@ use ckb_std::{default_alloc, entry};
 entry!(entry);
 default_alloc!();
 
-mod validator;
-
 /// Program entry
 fn entry() -> i8 {
     // Call main function and return error code
-    match validator::validate() {
+    match nft_validator_core::validate() {
         Ok(_) => 0,
         Err(err) => err as i8,
     }
@@ -0,0 +1,299 @@
+//! Applies structured mutations to the mock transactions dumped by the tests,
+//! then runs the mutated transaction through the `nft-validator-sim` binary
+//! and checks its exit code against the NFT rules run in-process on a
+//! `MockChain`, failing on crashes, panics or diverging verdicts.
+//!
+//! `SIM_BINARY` is the native simulator, e.g. `../build/debug/nft-validator-sim`,
+//! and `FUZZ_SEEDS` the `dumped_tests` folder the seed transactions are read from.
+#![no_main]
+use arbitrary::Arbitrary;
+use ckb_standalone_debugger::transaction::{MockInput, MockTransaction, ReprMockTransaction};
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    packed::{Byte32, BytesVec, CellInputVec, CellOutput, CellOutputVec, Script},
+    prelude::*,
+};
+use lazy_static::lazy_static;
+use libfuzzer_sys::fuzz_target;
+use nft_validator_core::{
+    mock::{MockCell, MockChain},
+    validate_with,
+};
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A dumped test case running a type script.
+struct Seed {
+    tx: MockTransaction,
+    setup_file: PathBuf,
+    is_output: bool,
+    script_index: usize,
+}
+
+lazy_static! {
+    static ref SEEDS: Vec<Seed> = load_seeds();
+}
+
+fn load_seeds() -> Vec<Seed> {
+    let folder = env::var("FUZZ_SEEDS").expect("FUZZ_SEEDS");
+    let mut seeds = Vec::new();
+    for entry in fs::read_dir(folder).expect("read seed folder") {
+        let case = entry.expect("read seed folder").path();
+        let setup_file = case.join("setup.json");
+        let (tx_json, setup_json) = match (fs::read(case.join("tx.json")), fs::read(&setup_file)) {
+            (Ok(tx_json), Ok(setup_json)) => (tx_json, setup_json),
+            _ => continue,
+        };
+        let setup: Value = serde_json::from_slice(&setup_json).expect("parse setup");
+        if setup["is_lock_script"].as_bool().expect("is_lock_script") {
+            continue;
+        }
+        let repr: ReprMockTransaction = serde_json::from_slice(&tx_json).expect("parse tx");
+        seeds.push(Seed {
+            tx: repr.into(),
+            setup_file,
+            is_output: setup["is_output"].as_bool().expect("is_output"),
+            script_index: setup["script_index"].as_u64().expect("script_index") as usize,
+        });
+    }
+    assert!(!seeds.is_empty(), "no seed transactions found");
+    seeds
+}
+
+/// A change to the inputs or outputs of a seed transaction, indices wrap
+/// around the number of cells.
+#[derive(Arbitrary, Debug)]
+enum Mutation {
+    DropInput(u8),
+    DuplicateInput(u8),
+    DropOutput(u8),
+    DuplicateOutput(u8),
+    SwapOutputs(u8, u8),
+    FlipOutputData { index: u8, offset: u16, mask: u8 },
+    TruncateOutputData { index: u8, length: u16 },
+    SetOutputData { index: u8, data: Vec<u8> },
+    SetOutputCapacity { index: u8, capacity: u64 },
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzCase {
+    seed: u8,
+    mutations: Vec<Mutation>,
+}
+
+/// The editable parts of a mock transaction.
+struct Cells {
+    inputs: Vec<MockInput>,
+    outputs: Vec<CellOutput>,
+    outputs_data: Vec<Bytes>,
+}
+
+impl Cells {
+    fn new(tx: &MockTransaction) -> Self {
+        let raw = tx.tx.raw();
+        Cells {
+            inputs: tx.mock_info.inputs.clone(),
+            outputs: raw.outputs().into_iter().collect(),
+            outputs_data: raw
+                .outputs_data()
+                .into_iter()
+                .map(|data| data.raw_data())
+                .collect(),
+        }
+    }
+
+    fn apply(&mut self, mutation: &Mutation) {
+        let inputs = self.inputs.len();
+        let outputs = self.outputs.len();
+        let wrap = |index: u8, len: usize| index as usize % len;
+        match mutation {
+            Mutation::DropInput(index) if inputs > 0 => {
+                self.inputs.remove(wrap(*index, inputs));
+            }
+            Mutation::DuplicateInput(index) if inputs > 0 => {
+                let input = self.inputs[wrap(*index, inputs)].clone();
+                self.inputs.push(input);
+            }
+            Mutation::DropOutput(index) if outputs > 0 => {
+                let index = wrap(*index, outputs);
+                self.outputs.remove(index);
+                self.outputs_data.remove(index);
+            }
+            Mutation::DuplicateOutput(index) if outputs > 0 => {
+                let index = wrap(*index, outputs);
+                self.outputs.push(self.outputs[index].clone());
+                self.outputs_data.push(self.outputs_data[index].clone());
+            }
+            Mutation::SwapOutputs(a, b) if outputs > 0 => {
+                let (a, b) = (wrap(*a, outputs), wrap(*b, outputs));
+                self.outputs.swap(a, b);
+                self.outputs_data.swap(a, b);
+            }
+            Mutation::FlipOutputData {
+                index,
+                offset,
+                mask,
+            } if outputs > 0 => {
+                let data = &mut self.outputs_data[wrap(*index, outputs)];
+                if !data.is_empty() {
+                    let mut flipped = data.to_vec();
+                    flipped[*offset as usize % data.len()] ^= mask;
+                    *data = flipped.into();
+                }
+            }
+            Mutation::TruncateOutputData { index, length } if outputs > 0 => {
+                let data = &mut self.outputs_data[wrap(*index, outputs)];
+                data.truncate(*length as usize);
+            }
+            Mutation::SetOutputData { index, data } if outputs > 0 => {
+                self.outputs_data[wrap(*index, outputs)] = data.clone().into();
+            }
+            Mutation::SetOutputCapacity { index, capacity } if outputs > 0 => {
+                let output = &mut self.outputs[wrap(*index, outputs)];
+                *output = output
+                    .clone()
+                    .as_builder()
+                    .capacity(capacity.pack())
+                    .build();
+            }
+            _ => {}
+        }
+    }
+
+    fn build(self, seed: &MockTransaction) -> MockTransaction {
+        let raw = seed
+            .tx
+            .raw()
+            .as_builder()
+            .inputs(
+                CellInputVec::new_builder()
+                    .set(
+                        self.inputs
+                            .iter()
+                            .map(|input| input.input.clone())
+                            .collect(),
+                    )
+                    .build(),
+            )
+            .outputs(CellOutputVec::new_builder().set(self.outputs).build())
+            .outputs_data(
+                BytesVec::new_builder()
+                    .set(self.outputs_data.iter().map(|data| data.pack()).collect())
+                    .build(),
+            )
+            .build();
+        let mut mock_tx = seed.clone();
+        mock_tx.mock_info.inputs = self.inputs;
+        mock_tx.tx = seed.tx.clone().as_builder().raw(raw).build();
+        mock_tx
+    }
+}
+
+fn hash(hash: Byte32) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(hash.as_slice());
+    buf
+}
+
+fn mock_cell(output: &CellOutput, data: &[u8]) -> MockCell {
+    MockCell {
+        capacity: output.capacity().unpack(),
+        lock_hash: hash(output.lock().calc_script_hash()),
+        type_hash: output
+            .type_()
+            .to_opt()
+            .map(|script| hash(script.calc_script_hash())),
+        data: data.to_vec(),
+    }
+}
+
+/// Builds the `MockChain` of `mock_tx` running `script`.
+fn mock_chain(mock_tx: &MockTransaction, script: &Script) -> MockChain {
+    let raw = mock_tx.tx.raw();
+    let outputs_data = raw.outputs_data();
+    MockChain {
+        script_args: script.args().raw_data().to_vec(),
+        script_hash: hash(script.calc_script_hash()),
+        cell_inputs: raw
+            .inputs()
+            .into_iter()
+            .map(|input| input.as_slice().to_vec())
+            .collect(),
+        inputs: mock_tx
+            .mock_info
+            .inputs
+            .iter()
+            .map(|input| mock_cell(&input.output, &input.data))
+            .collect(),
+        outputs: raw
+            .outputs()
+            .into_iter()
+            .enumerate()
+            .map(|(index, output)| {
+                let data = outputs_data
+                    .get(index)
+                    .map(|data| data.raw_data())
+                    .unwrap_or_default();
+                mock_cell(&output, &data)
+            })
+            .collect(),
+        cell_deps: mock_tx
+            .mock_info
+            .cell_deps
+            .iter()
+            .map(|cell_dep| mock_cell(&cell_dep.output, &cell_dep.data))
+            .collect(),
+    }
+}
+
+fuzz_target!(|case: FuzzCase| {
+    let seed = &SEEDS[case.seed as usize % SEEDS.len()];
+    let mut cells = Cells::new(&seed.tx);
+    for mutation in &case.mutations {
+        cells.apply(mutation);
+    }
+    let mock_tx = cells.build(&seed.tx);
+
+    // The seed's setup keeps selecting the script by index, skip mutants
+    // where no type script is left there.
+    let cell = if seed.is_output {
+        mock_tx.tx.raw().outputs().get(seed.script_index)
+    } else {
+        mock_tx
+            .mock_info
+            .inputs
+            .get(seed.script_index)
+            .map(|input| input.output.clone())
+    };
+    let script = match cell.and_then(|cell| cell.type_().to_opt()) {
+        Some(script) => script,
+        None => return,
+    };
+    let expected = match validate_with(&mock_chain(&mock_tx, &script)) {
+        Ok(()) => 0,
+        Err(err) => err as i32,
+    };
+
+    let binary = env::var("SIM_BINARY").expect("SIM_BINARY");
+    let repr_tx: ReprMockTransaction = mock_tx.into();
+    let mut tx_file = tempfile::NamedTempFile::new().expect("create tx file");
+    tx_file
+        .write_all(&serde_json::to_vec(&repr_tx).expect("serialize tx"))
+        .expect("write tx file");
+    let output = Command::new(&binary)
+        .env("CKB_TX_FILE", tx_file.path())
+        .env("CKB_RUNNING_SETUP", &seed.setup_file)
+        .output()
+        .expect("run native simulator");
+    let code = output.status.code().expect("native simulator crashed");
+    assert_eq!(
+        code,
+        expected,
+        "simulator verdict diverges from the NFT rules, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+});
@@ -0,0 +1,43 @@
+//! Mutates dumped mock transactions and runs them through a native simulator
+//! binary and its `.asan` build, failing on crashes, sanitizer reports or
+//! when both builds disagree on the exit code.
+//!
+//! `SIM_BINARY` is the native binary to run, e.g. `../c/build/debug/simple_udt_sim`,
+//! and `SIM_SETUP` the `setup.json` of a dumped case selecting the script to run.
+#![no_main]
+use ckb_standalone_debugger::transaction::ReprMockTransaction;
+use libfuzzer_sys::fuzz_target;
+use std::env;
+use std::io::Write;
+use std::process::{Command, Output};
+
+fn run(binary: &str, tx_file: &str, setup_file: &str) -> Output {
+    Command::new(binary)
+        .env("CKB_TX_FILE", tx_file)
+        .env("CKB_RUNNING_SETUP", setup_file)
+        .output()
+        .expect("run native simulator")
+}
+
+fuzz_target!(|data: &[u8]| {
+    if serde_json::from_slice::<ReprMockTransaction>(data).is_err() {
+        return;
+    }
+    let binary = env::var("SIM_BINARY").expect("SIM_BINARY");
+    let setup_file = env::var("SIM_SETUP").expect("SIM_SETUP");
+    let mut tx_file = tempfile::NamedTempFile::new().expect("create tx file");
+    tx_file.write_all(data).expect("write tx file");
+    let tx_path = tx_file.path().to_str().expect("utf8");
+
+    let plain = run(&binary, tx_path, &setup_file);
+    let asan = run(&format!("{}.asan", binary), tx_path, &setup_file);
+    let asan_stderr = String::from_utf8_lossy(&asan.stderr);
+    assert!(
+        !asan_stderr.contains("AddressSanitizer"),
+        "sanitizer report: {}",
+        asan_stderr
+    );
+    let plain_code = plain.status.code().expect("native simulator crashed");
+    let asan_code = asan.status.code().expect("asan simulator crashed");
+    assert_eq!(plain_code, asan_code, "asan build diverges");
+});
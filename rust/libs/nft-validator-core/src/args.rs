@@ -0,0 +1,252 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::{convert::TryInto, result::Result};
+
+// Import heap related library from `alloc`
+// https://doc.rust-lang.org/alloc/index.html
+use alloc::{collections::btree_set::BTreeSet, vec, vec::Vec};
+
+use crate::error::Error;
+
+/// Owners are allowed to burn their NFTs without governance mode.
+pub const FLAG_OWNER_BURN: u8 = 0x1;
+/// A supply section follows the flags byte, see `SupplyConfig`.
+pub const FLAG_SUPPLY_CAP: u8 = 0x2;
+/// A multi governance section follows the flags byte, see `ScriptArgs`.
+pub const FLAG_MULTI_GOVERNANCE: u8 = 0x4;
+/// A royalty section follows the flags byte, see `RoyaltyConfig`.
+pub const FLAG_ROYALTY: u8 = 0x8;
+/// A class section follows the flags byte, see `ScriptArgs`.
+pub const FLAG_CLASS: u8 = 0x10;
+/// An ID scheme section follows the flags byte, see `IdScheme`.
+pub const FLAG_ID_SCHEME: u8 = 0x20;
+/// The script governs a class cell instead of NFTs, a class cell section
+/// follows the flags byte, see `ScriptArgs`.
+pub const FLAG_CLASS_CELL: u8 = 0x40;
+
+const KNOWN_FLAGS: u8 = FLAG_OWNER_BURN
+    | FLAG_SUPPLY_CAP
+    | FLAG_MULTI_GOVERNANCE
+    | FLAG_ROYALTY
+    | FLAG_CLASS
+    | FLAG_ID_SCHEME
+    | FLAG_CLASS_CELL;
+
+/// Version byte of `IdScheme::InputBased` in the ID scheme section.
+pub const ID_SCHEME_INPUT_BASED: u8 = 0;
+/// Version byte of `IdScheme::ClassSerial` in the ID scheme section.
+pub const ID_SCHEME_CLASS_SERIAL: u8 = 1;
+
+/// Parsed script args of nft-validator, the layout is:
+///
+/// | governance lock hash (32 bytes) | flags (1 byte) | sections |
+///
+/// Both the flags byte and the sections are optional. Each section is
+/// enabled by a bit in the flags byte, enabled sections follow the flags
+/// byte in the order of their flag bits.
+///
+/// Multi governance section, enabled by `FLAG_MULTI_GOVERNANCE`:
+///
+/// | threshold (u8) | N (u8) | N additional governance lock hashes |
+///
+/// Together with the leading governance lock hash, this forms a list of N + 1
+/// distinct governance locks, governance mode then requires at least
+/// threshold of them among the input locks. Without the section, the
+/// leading governance lock alone enables governance mode.
+///
+/// Class section, enabled by `FLAG_CLASS`:
+///
+/// | class type hash (32 bytes) |
+///
+/// NFTs then belong to the collection described by the class cell using
+/// this type script, see `ClassData` in `schemas/nft.mol`.
+///
+/// Class cell section, enabled by `FLAG_CLASS_CELL`:
+///
+/// | class ID (32 bytes) |
+///
+/// The script then governs the class cell of a collection rather than NFTs,
+/// the class ID is derived the same way as NFT IDs. Only the multi
+/// governance section can be combined with this one.
+pub struct ScriptArgs {
+    pub governance_lock_hashes: Vec<[u8; 32]>,
+    pub governance_threshold: usize,
+    pub flags: u8,
+    pub supply: Option<SupplyConfig>,
+    pub royalty: Option<RoyaltyConfig>,
+    pub class_type_hash: Option<[u8; 32]>,
+    pub id_scheme: IdScheme,
+    pub class_cell_id: Option<[u8; 32]>,
+}
+
+/// Supply section, enabled by `FLAG_SUPPLY_CAP`:
+///
+/// | max supply (u64 LE) | mint limit per transaction (u64 LE) | counter ID (32 bytes) |
+///
+/// A mint limit of 0 means there is no per transaction limit. The counter ID
+/// identifies the only supply counter cell that can ever be created for
+/// current NFT type, it is derived the same way as NFT IDs.
+pub struct SupplyConfig {
+    pub max_supply: u64,
+    pub mint_limit: u64,
+    pub counter_id: [u8; 32],
+}
+
+/// ID scheme section, enabled by `FLAG_ID_SCHEME`:
+///
+/// | scheme version (u8) |
+///
+/// Version 0 is the input based scheme, which is also used when the section
+/// is absent. Version 1 is the class serial scheme, it requires the class
+/// section.
+#[derive(Clone, Copy)]
+pub enum IdScheme {
+    /// IDs are derived from the first input of the generating transaction
+    /// and the output index of the generated cell.
+    InputBased,
+    /// IDs are derived from the class type hash and a serial number, which
+    /// is the issued counter of the class cell at generation time. Serials
+    /// are only unique when the class cell is governed by nft-validator
+    /// with `FLAG_CLASS_CELL`, which never lets the counter go down.
+    ClassSerial([u8; 32]),
+}
+
+/// Royalty section, enabled by `FLAG_ROYALTY`:
+///
+/// | royalty lock hash (32 bytes) | royalty per transferred NFT (u64 LE, in shannons) |
+///
+/// Transactions transferring NFTs outside governance mode must include
+/// outputs using the royalty lock, with a total capacity of at least the
+/// royalty times the number of transferred NFTs.
+pub struct RoyaltyConfig {
+    pub lock_hash: [u8; 32],
+    pub amount: u64,
+}
+
+impl ScriptArgs {
+    pub fn parse(args: &[u8]) -> Result<Self, Error> {
+        let mut cursor = Cursor(args);
+        let mut governance_lock_hashes = vec![cursor.read_hash()?];
+        let flags = if cursor.is_empty() {
+            0
+        } else {
+            cursor.read_u8()?
+        };
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(Error::InvalidArgument);
+        }
+        let supply = if flags & FLAG_SUPPLY_CAP != 0 {
+            Some(SupplyConfig {
+                max_supply: cursor.read_u64()?,
+                mint_limit: cursor.read_u64()?,
+                counter_id: cursor.read_hash()?,
+            })
+        } else {
+            None
+        };
+        let mut governance_threshold = 1;
+        if flags & FLAG_MULTI_GOVERNANCE != 0 {
+            governance_threshold = cursor.read_u8()? as usize;
+            let count = cursor.read_u8()?;
+            for _ in 0..count {
+                governance_lock_hashes.push(cursor.read_hash()?);
+            }
+            let distinct_hashes: BTreeSet<&[u8; 32]> = governance_lock_hashes.iter().collect();
+            if governance_threshold == 0
+                || governance_threshold > governance_lock_hashes.len()
+                || distinct_hashes.len() != governance_lock_hashes.len()
+            {
+                return Err(Error::InvalidArgument);
+            }
+        }
+        let royalty = if flags & FLAG_ROYALTY != 0 {
+            Some(RoyaltyConfig {
+                lock_hash: cursor.read_hash()?,
+                amount: cursor.read_u64()?,
+            })
+        } else {
+            None
+        };
+        let class_type_hash = if flags & FLAG_CLASS != 0 {
+            Some(cursor.read_hash()?)
+        } else {
+            None
+        };
+        let id_scheme = if flags & FLAG_ID_SCHEME != 0 {
+            match (cursor.read_u8()?, class_type_hash) {
+                (ID_SCHEME_INPUT_BASED, _) => IdScheme::InputBased,
+                (ID_SCHEME_CLASS_SERIAL, Some(class_type_hash)) => {
+                    IdScheme::ClassSerial(class_type_hash)
+                }
+                _ => return Err(Error::InvalidArgument),
+            }
+        } else {
+            IdScheme::InputBased
+        };
+        let class_cell_id = if flags & FLAG_CLASS_CELL != 0 {
+            if flags & !(FLAG_CLASS_CELL | FLAG_MULTI_GOVERNANCE) != 0 {
+                return Err(Error::InvalidArgument);
+            }
+            Some(cursor.read_hash()?)
+        } else {
+            None
+        };
+        if !cursor.is_empty() {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(ScriptArgs {
+            governance_lock_hashes,
+            governance_threshold,
+            flags,
+            supply,
+            royalty,
+            class_type_hash,
+            id_scheme,
+            class_cell_id,
+        })
+    }
+
+    /// Governance mode is enabled when enough governance locks are present
+    /// in the input cells of current transaction.
+    pub fn governance_mode(&self, input_lock_hashes: &BTreeSet<[u8; 32]>) -> bool {
+        let present = self
+            .governance_lock_hashes
+            .iter()
+            .filter(|lock_hash| input_lock_hashes.contains(*lock_hash))
+            .count();
+        present >= self.governance_threshold
+    }
+
+    pub fn owner_burn_allowed(&self) -> bool {
+        self.flags & FLAG_OWNER_BURN != 0
+    }
+}
+
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < length {
+            return Err(Error::InvalidArgument);
+        }
+        let (head, tail) = self.0.split_at(length);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        self.take(1).map(|data| data[0])
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Error> {
+        self.take(8)
+            .map(|data| u64::from_le_bytes(data.try_into().expect("u64")))
+    }
+
+    fn read_hash(&mut self) -> Result<[u8; 32], Error> {
+        self.take(32).map(|data| data.try_into().expect("hash"))
+    }
+}
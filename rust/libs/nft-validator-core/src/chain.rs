@@ -0,0 +1,77 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+// Import heap related library from `alloc`
+// https://doc.rust-lang.org/alloc/index.html
+use alloc::vec::Vec;
+
+// Import CKB syscalls and structures
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    error::SysError,
+    high_level,
+};
+
+/// The part of a transaction the validator reads. `CkbChain` loads it via
+/// CKB syscalls, while `MockChain` keeps it in memory, so the validator can
+/// be unit tested without CKB-VM or the x64 simulator.
+///
+/// Indices and sources follow CKB syscall semantics, loading past the last
+/// cell of a source returns `SysError::IndexOutOfBound`.
+pub trait ChainView {
+    /// Args of the running script.
+    fn load_script_args(&self) -> Result<Vec<u8>, SysError>;
+    /// Hash of the running script.
+    fn load_script_hash(&self) -> Result<[u8; 32], SysError>;
+    /// Molecule serialized CellInput of the input at `index`.
+    fn load_input(&self, index: usize) -> Result<Vec<u8>, SysError>;
+    fn load_cell_data(&self, index: usize, source: Source) -> Result<Vec<u8>, SysError>;
+    fn load_cell_capacity(&self, index: usize, source: Source) -> Result<u64, SysError>;
+    fn load_cell_lock_hash(&self, index: usize, source: Source) -> Result<[u8; 32], SysError>;
+    fn load_cell_type_hash(
+        &self,
+        index: usize,
+        source: Source,
+    ) -> Result<Option<[u8; 32]>, SysError>;
+}
+
+/// `ChainView` of the transaction being verified by CKB.
+pub struct CkbChain;
+
+impl ChainView for CkbChain {
+    fn load_script_args(&self) -> Result<Vec<u8>, SysError> {
+        let args: Bytes = high_level::load_script()?.args().unpack();
+        Ok(args.to_vec())
+    }
+
+    fn load_script_hash(&self) -> Result<[u8; 32], SysError> {
+        high_level::load_script_hash()
+    }
+
+    fn load_input(&self, index: usize) -> Result<Vec<u8>, SysError> {
+        let input = high_level::load_input(index, Source::Input)?;
+        Ok(input.as_slice().to_vec())
+    }
+
+    fn load_cell_data(&self, index: usize, source: Source) -> Result<Vec<u8>, SysError> {
+        high_level::load_cell_data(index, source)
+    }
+
+    fn load_cell_capacity(&self, index: usize, source: Source) -> Result<u64, SysError> {
+        high_level::load_cell_capacity(index, source)
+    }
+
+    fn load_cell_lock_hash(&self, index: usize, source: Source) -> Result<[u8; 32], SysError> {
+        high_level::load_cell_lock_hash(index, source)
+    }
+
+    fn load_cell_type_hash(
+        &self,
+        index: usize,
+        source: Source,
+    ) -> Result<Option<[u8; 32]>, SysError> {
+        high_level::load_cell_type_hash(index, source)
+    }
+}
@@ -0,0 +1,56 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::{convert::TryInto, result::Result};
+
+use molecule::prelude::*;
+
+use crate::error::Error;
+use crate::schemas::nft::NftDataReader;
+
+/// Supply counter cells keep the number of issued NFTs as a u64 value in
+/// little endian, NFT data is always longer than that.
+pub const SUPPLY_COUNTER_SIZE: usize = 8;
+
+/// Size of an NftData structure with empty metadata and no URI: the table
+/// header with 5 offsets, 2 hashes, an empty metadata vector and flags.
+pub const NFT_DATA_MIN_SIZE: usize = 4 * 6 + 32 + 32 + 4 + 1;
+
+/// Frozen NFTs can only be transferred in governance mode, governance can
+/// freeze or unfreeze an NFT by updating its flags.
+pub const NFT_FLAG_FROZEN: u8 = 0x1;
+/// Soulbound NFTs are bound to their owners, they are treated the same as
+/// frozen NFTs, but are not expected to be unfrozen later.
+pub const NFT_FLAG_SOULBOUND: u8 = 0x2;
+
+const KNOWN_NFT_FLAGS: u8 = NFT_FLAG_FROZEN | NFT_FLAG_SOULBOUND;
+
+/// NFT cells keep a molecule serialized NftData structure in cell data, with
+/// no flags set other than the known ones.
+pub fn parse_nft_data(data: &[u8]) -> Result<NftDataReader, Error> {
+    if data.len() < NFT_DATA_MIN_SIZE {
+        return Err(Error::NftDataTooShort);
+    }
+    let reader = NftDataReader::from_slice(data).map_err(|_| Error::MalformedNftData)?;
+    if reader.flags().as_slice()[0] & !KNOWN_NFT_FLAGS != 0 {
+        return Err(Error::MalformedNftData);
+    }
+    Ok(reader)
+}
+
+/// The first field of NftData is the NFT ID.
+pub fn parse_nft_id(data: &[u8]) -> Result<[u8; 32], Error> {
+    let reader = parse_nft_data(data)?;
+    let mut nft_id = [0u8; 32];
+    nft_id.copy_from_slice(reader.id().raw_data());
+    Ok(nft_id)
+}
+
+/// Flags of an NFT, cell data must already be verified via `parse_nft_data`.
+pub(crate) fn parse_nft_flags(data: &[u8]) -> u8 {
+    NftDataReader::new_unchecked(data).flags().as_slice()[0]
+}
+
+pub fn parse_supply_counter(data: &[u8]) -> Result<u64, Error> {
+    data.try_into()
+        .map(u64::from_le_bytes)
+        .map_err(|_| Error::InvalidSupplyCounter)
+}
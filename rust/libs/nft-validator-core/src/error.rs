@@ -0,0 +1,66 @@
+// Import CKB syscall errors
+use ckb_std::error::SysError;
+
+/// Error
+///
+/// The discriminants are the exit codes of the script, so new errors must
+/// only be appended. Enable the `debug-trace` feature to also log the index
+/// of the offending cell when an error is returned.
+#[derive(Debug, PartialEq, Eq)]
+#[repr(i8)]
+pub enum Error {
+    IndexOutOfBound = 1,
+    ItemMissing,
+    LengthNotEnough,
+    Encoding,
+    // Add customized errors here...
+    /// Script args can not be parsed.
+    InvalidArgument,
+    /// NFT or class cell generation without governance mode.
+    RequireGovernanceMode,
+    /// The ID of a generated NFT is not the hash derived from the first
+    /// input and its output index.
+    InvalidNft,
+    /// An NFT is burned without governance mode or owner burn permission.
+    UnauthorizedBurn,
+    /// The same NFT ID appears more than once in the outputs.
+    DuplicateNft,
+    /// NFT data is changed in a transfer without governance mode.
+    ImmutableNftData,
+    /// The supply counter cell is missing, duplicated, or has a wrong count.
+    InvalidSupplyCounter,
+    /// More NFTs are issued than the supply cap or class max supply allows.
+    SupplyCapExceeded,
+    /// More NFTs are generated in one transaction than the mint limit allows.
+    MintLimitExceeded,
+    /// A frozen or soulbound NFT is transferred without governance mode.
+    NftFrozen,
+    /// Outputs using the royalty lock do not cover the royalty.
+    RoyaltyNotPaid,
+    /// The class cell is missing, duplicated, or incorrectly updated.
+    InvalidClassCell,
+    /// A syscall returned an error code unknown to ckb-std.
+    UnknownSysError,
+    /// NFT data is shorter than the smallest valid NftData structure.
+    NftDataTooShort,
+    /// NFT data is not a valid NftData structure, or sets unknown flags.
+    MalformedNftData,
+    /// Class cell data is not a valid ClassData structure.
+    MalformedClassData,
+}
+
+impl From<SysError> for Error {
+    fn from(err: SysError) -> Self {
+        use SysError::*;
+        match err {
+            IndexOutOfBound => Self::IndexOutOfBound,
+            ItemMissing => Self::ItemMissing,
+            LengthNotEnough(_) => Self::LengthNotEnough,
+            Encoding => Self::Encoding,
+            Unknown(err_code) => {
+                trace!("unexpected sys error {}", err_code);
+                Self::UnknownSysError
+            }
+        }
+    }
+}
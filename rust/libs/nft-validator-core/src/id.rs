@@ -0,0 +1,37 @@
+use blake2b_rs::Blake2bBuilder;
+
+use crate::args::IdScheme;
+
+/// IDs are the blake2b hash of the first input of the generating
+/// transaction, and the output index of the generated cell. `first_input`
+/// is the molecule serialized CellInput.
+pub fn derive_id(first_input: &[u8], index: usize) -> [u8; 32] {
+    ckb_hash(&[first_input, &(index as u64).to_le_bytes()])
+}
+
+/// Class serial IDs are the blake2b hash of the class type hash, and the
+/// serial number of the NFT in its class.
+pub fn derive_serial_id(class_type_hash: &[u8; 32], serial: u64) -> [u8; 32] {
+    ckb_hash(&[class_type_hash, &serial.to_le_bytes()])
+}
+
+/// Expected ID of an NFT generated at output `index`, `serial` is its class
+/// serial number, which is only used by `IdScheme::ClassSerial`.
+pub fn derive_nft_id(scheme: IdScheme, first_input: &[u8], index: usize, serial: u64) -> [u8; 32] {
+    match scheme {
+        IdScheme::InputBased => derive_id(first_input, index),
+        IdScheme::ClassSerial(class_type_hash) => derive_serial_id(&class_type_hash, serial),
+    }
+}
+
+fn ckb_hash(parts: &[&[u8]]) -> [u8; 32] {
+    let mut blake2b = Blake2bBuilder::new(32)
+        .personal(b"ckb-default-hash")
+        .build();
+    for part in parts {
+        blake2b.update(part);
+    }
+    let mut hash = [0u8; 32];
+    blake2b.finalize(&mut hash[..]);
+    hash
+}
@@ -0,0 +1,32 @@
+//! NFT rules of nft-validator, shared by the nft-validator contract, its
+//! native simulator, and other contracts that need to validate NFT cells.
+//!
+//! `validate` runs the complete validation for the current script group,
+//! while `args`, `data` and `id` expose script args parsing, cell data
+//! parsing and NFT ID derivation respectively. `validate_with` runs the
+//! same validation against any `ChainView`, such as the in-memory
+//! `MockChain` enabled by the `mock` feature.
+
+#![no_std]
+
+extern crate alloc;
+
+#[macro_use]
+mod trace;
+
+pub mod args;
+pub mod chain;
+pub mod data;
+pub mod error;
+pub mod id;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod schemas;
+mod validator;
+
+#[cfg(test)]
+mod tests;
+
+pub use chain::{ChainView, CkbChain};
+pub use error::Error;
+pub use validator::{validate, validate_with};
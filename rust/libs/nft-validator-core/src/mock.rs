@@ -0,0 +1,90 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+// Import heap related library from `alloc`
+// https://doc.rust-lang.org/alloc/index.html
+use alloc::vec::Vec;
+
+use ckb_std::{ckb_constants::Source, error::SysError};
+
+use crate::chain::ChainView;
+
+/// A cell kept in `MockChain`.
+#[derive(Clone, Default)]
+pub struct MockCell {
+    pub capacity: u64,
+    pub lock_hash: [u8; 32],
+    pub type_hash: Option<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+/// In-memory `ChainView` for unit tests. The running script is taken as a
+/// type script, its group consists of the cells whose type hash equals
+/// `script_hash`.
+#[derive(Clone, Default)]
+pub struct MockChain {
+    pub script_args: Vec<u8>,
+    pub script_hash: [u8; 32],
+    /// Molecule serialized CellInput of each input, in the order of `inputs`.
+    pub cell_inputs: Vec<Vec<u8>>,
+    pub inputs: Vec<MockCell>,
+    pub outputs: Vec<MockCell>,
+    pub cell_deps: Vec<MockCell>,
+}
+
+impl MockChain {
+    fn cell(&self, index: usize, source: Source) -> Result<&MockCell, SysError> {
+        let cell = match source {
+            Source::Input => self.inputs.get(index),
+            Source::Output => self.outputs.get(index),
+            Source::CellDep => self.cell_deps.get(index),
+            Source::GroupInput => self.group_cells(&self.inputs).nth(index),
+            Source::GroupOutput => self.group_cells(&self.outputs).nth(index),
+            Source::HeaderDep => return Err(SysError::ItemMissing),
+        };
+        cell.ok_or(SysError::IndexOutOfBound)
+    }
+
+    fn group_cells<'a>(&'a self, cells: &'a [MockCell]) -> impl Iterator<Item = &'a MockCell> {
+        cells
+            .iter()
+            .filter(move |cell| cell.type_hash == Some(self.script_hash))
+    }
+}
+
+impl ChainView for MockChain {
+    fn load_script_args(&self) -> Result<Vec<u8>, SysError> {
+        Ok(self.script_args.clone())
+    }
+
+    fn load_script_hash(&self) -> Result<[u8; 32], SysError> {
+        Ok(self.script_hash)
+    }
+
+    fn load_input(&self, index: usize) -> Result<Vec<u8>, SysError> {
+        self.cell_inputs
+            .get(index)
+            .cloned()
+            .ok_or(SysError::IndexOutOfBound)
+    }
+
+    fn load_cell_data(&self, index: usize, source: Source) -> Result<Vec<u8>, SysError> {
+        self.cell(index, source).map(|cell| cell.data.clone())
+    }
+
+    fn load_cell_capacity(&self, index: usize, source: Source) -> Result<u64, SysError> {
+        self.cell(index, source).map(|cell| cell.capacity)
+    }
+
+    fn load_cell_lock_hash(&self, index: usize, source: Source) -> Result<[u8; 32], SysError> {
+        self.cell(index, source).map(|cell| cell.lock_hash)
+    }
+
+    fn load_cell_type_hash(
+        &self,
+        index: usize,
+        source: Source,
+    ) -> Result<Option<[u8; 32]>, SysError> {
+        self.cell(index, source).map(|cell| cell.type_hash)
+    }
+}
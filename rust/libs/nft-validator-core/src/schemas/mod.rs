@@ -0,0 +1,11 @@
+//! Rust bindings for the molecule schemas in `schemas/`, the submodules
+//! here are generated by moleculec, run `make schemas` to regenerate them.
+
+pub mod blockchain {
+    pub use ckb_std::ckb_types::packed::{
+        Byte32, Byte32Reader, Bytes, BytesOpt, BytesOptReader, BytesReader, Uint64, Uint64Reader,
+    };
+}
+
+#[allow(clippy::all)]
+pub mod nft;
@@ -0,0 +1,422 @@
+use alloc::{vec, vec::Vec};
+
+use molecule::prelude::*;
+
+use crate::args::{
+    FLAG_CLASS, FLAG_CLASS_CELL, FLAG_ID_SCHEME, FLAG_OWNER_BURN, FLAG_ROYALTY,
+    ID_SCHEME_CLASS_SERIAL,
+};
+use crate::data::NFT_FLAG_FROZEN;
+use crate::id::{derive_id, derive_serial_id};
+use crate::mock::{MockCell, MockChain};
+use crate::schemas::blockchain::{Byte32, Bytes, Uint64};
+use crate::schemas::nft::{ClassData, NftData};
+use crate::{validate_with, Error};
+
+const NFT_TYPE_HASH: [u8; 32] = [1u8; 32];
+const GOVERNANCE_LOCK_HASH: [u8; 32] = [2u8; 32];
+const OWNER_LOCK_HASH: [u8; 32] = [3u8; 32];
+const BUYER_LOCK_HASH: [u8; 32] = [4u8; 32];
+const CLASS_TYPE_HASH: [u8; 32] = [5u8; 32];
+
+fn build_args(flags: u8, sections: &[&[u8]]) -> Vec<u8> {
+    let mut args = GOVERNANCE_LOCK_HASH.to_vec();
+    args.push(flags);
+    for section in sections {
+        args.extend_from_slice(section);
+    }
+    args
+}
+
+fn build_bytes(data: &[u8]) -> Bytes {
+    Bytes::new_builder()
+        .set(data.iter().map(|b| Byte::new(*b)).collect())
+        .build()
+}
+
+fn build_nft_data(nft_id: &[u8; 32], metadata: &[u8], flags: u8) -> Vec<u8> {
+    NftData::new_builder()
+        .id(Byte32::from_slice(nft_id).expect("nft id"))
+        .metadata(build_bytes(metadata))
+        .flags(Byte::new(flags))
+        .build()
+        .as_slice()
+        .to_vec()
+}
+
+fn build_class_data(issued: u64, max_supply: u64) -> Vec<u8> {
+    ClassData::new_builder()
+        .name(build_bytes(b"test"))
+        .issued(Uint64::from_slice(&issued.to_le_bytes()).expect("issued"))
+        .max_supply(Uint64::from_slice(&max_supply.to_le_bytes()).expect("max supply"))
+        .build()
+        .as_slice()
+        .to_vec()
+}
+
+fn cell(lock_hash: [u8; 32], type_hash: Option<[u8; 32]>, data: Vec<u8>) -> MockCell {
+    MockCell {
+        capacity: 1000,
+        lock_hash,
+        type_hash,
+        data,
+    }
+}
+
+fn nft_cell(lock_hash: [u8; 32], data: Vec<u8>) -> MockCell {
+    cell(lock_hash, Some(NFT_TYPE_HASH), data)
+}
+
+fn class_cell(issued: u64, max_supply: u64) -> MockCell {
+    cell(
+        GOVERNANCE_LOCK_HASH,
+        Some(CLASS_TYPE_HASH),
+        build_class_data(issued, max_supply),
+    )
+}
+
+fn build_chain(script_args: Vec<u8>, inputs: Vec<MockCell>, outputs: Vec<MockCell>) -> MockChain {
+    // Serialized CellInput is 44 bytes, any distinct content will do here
+    let cell_inputs = (0..inputs.len()).map(|i| vec![i as u8; 44]).collect();
+    MockChain {
+        script_args,
+        script_hash: NFT_TYPE_HASH,
+        cell_inputs,
+        inputs,
+        outputs,
+        cell_deps: Vec::new(),
+    }
+}
+
+/// Chain running the class cell script with `class_id`, whose hash is
+/// `CLASS_TYPE_HASH`.
+fn build_class_cell_chain(
+    class_id: &[u8; 32],
+    inputs: Vec<MockCell>,
+    outputs: Vec<MockCell>,
+) -> MockChain {
+    let mut chain = build_chain(build_args(FLAG_CLASS_CELL, &[class_id]), inputs, outputs);
+    chain.script_hash = CLASS_TYPE_HASH;
+    chain
+}
+
+#[test]
+fn test_transfer() {
+    let data = build_nft_data(&[7u8; 32], b"metadata", 0);
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![nft_cell(OWNER_LOCK_HASH, data.clone())],
+        vec![nft_cell(BUYER_LOCK_HASH, data)],
+    );
+    assert_eq!(validate_with(&chain), Ok(()));
+}
+
+#[test]
+fn test_transfer_with_modified_metadata() {
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![nft_cell(
+            OWNER_LOCK_HASH,
+            build_nft_data(&[7u8; 32], b"original", 0),
+        )],
+        vec![nft_cell(
+            BUYER_LOCK_HASH,
+            build_nft_data(&[7u8; 32], b"modified", 0),
+        )],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::ImmutableNftData));
+}
+
+#[test]
+fn test_transfer_frozen() {
+    let data = build_nft_data(&[7u8; 32], &[], NFT_FLAG_FROZEN);
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![nft_cell(OWNER_LOCK_HASH, data.clone())],
+        vec![nft_cell(BUYER_LOCK_HASH, data)],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::NftFrozen));
+}
+
+#[test]
+fn test_duplicate_transfer() {
+    let data = build_nft_data(&[7u8; 32], &[], 0);
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![nft_cell(OWNER_LOCK_HASH, data.clone())],
+        vec![
+            nft_cell(BUYER_LOCK_HASH, data.clone()),
+            nft_cell(OWNER_LOCK_HASH, data),
+        ],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::DuplicateNft));
+}
+
+#[test]
+fn test_transfer_with_duplicate_inputs() {
+    let data = build_nft_data(&[7u8; 32], &[], 0);
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![
+            nft_cell(OWNER_LOCK_HASH, data.clone()),
+            nft_cell(OWNER_LOCK_HASH, data.clone()),
+        ],
+        vec![nft_cell(BUYER_LOCK_HASH, data)],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::UnauthorizedBurn));
+}
+
+#[test]
+fn test_generation_in_governance_mode() {
+    let mut chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![cell(GOVERNANCE_LOCK_HASH, None, Vec::new())],
+        Vec::new(),
+    );
+    let nft_id = derive_id(&chain.cell_inputs[0], 1);
+    chain.outputs = vec![
+        cell(GOVERNANCE_LOCK_HASH, None, Vec::new()),
+        nft_cell(OWNER_LOCK_HASH, build_nft_data(&nft_id, &[], 0)),
+    ];
+    assert_eq!(validate_with(&chain), Ok(()));
+}
+
+#[test]
+fn test_generation_without_governance_mode() {
+    let mut chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![cell(OWNER_LOCK_HASH, None, Vec::new())],
+        Vec::new(),
+    );
+    let nft_id = derive_id(&chain.cell_inputs[0], 0);
+    chain.outputs = vec![nft_cell(OWNER_LOCK_HASH, build_nft_data(&nft_id, &[], 0))];
+    assert_eq!(validate_with(&chain), Err(Error::RequireGovernanceMode));
+}
+
+#[test]
+fn test_generation_with_invalid_id() {
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![cell(GOVERNANCE_LOCK_HASH, None, Vec::new())],
+        vec![nft_cell(
+            OWNER_LOCK_HASH,
+            build_nft_data(&[7u8; 32], &[], 0),
+        )],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::InvalidNft));
+}
+
+#[test]
+fn test_generation_with_short_nft_data() {
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![cell(GOVERNANCE_LOCK_HASH, None, Vec::new())],
+        vec![nft_cell(OWNER_LOCK_HASH, vec![0u8; 16])],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::NftDataTooShort));
+}
+
+#[test]
+fn test_generation_with_unknown_nft_flags() {
+    let mut chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![cell(GOVERNANCE_LOCK_HASH, None, Vec::new())],
+        Vec::new(),
+    );
+    let nft_id = derive_id(&chain.cell_inputs[0], 0);
+    chain.outputs = vec![nft_cell(
+        OWNER_LOCK_HASH,
+        build_nft_data(&nft_id, &[], 0xff),
+    )];
+    assert_eq!(validate_with(&chain), Err(Error::MalformedNftData));
+}
+
+#[test]
+fn test_generation_with_class_serial_ids() {
+    let args = build_args(
+        FLAG_CLASS | FLAG_ID_SCHEME,
+        &[&CLASS_TYPE_HASH, &[ID_SCHEME_CLASS_SERIAL]],
+    );
+    let chain = build_chain(
+        args,
+        vec![
+            cell(GOVERNANCE_LOCK_HASH, None, Vec::new()),
+            cell(
+                GOVERNANCE_LOCK_HASH,
+                Some(CLASS_TYPE_HASH),
+                build_class_data(3, 10),
+            ),
+        ],
+        vec![
+            cell(
+                GOVERNANCE_LOCK_HASH,
+                Some(CLASS_TYPE_HASH),
+                build_class_data(5, 10),
+            ),
+            nft_cell(
+                OWNER_LOCK_HASH,
+                build_nft_data(&derive_serial_id(&CLASS_TYPE_HASH, 3), &[], 0),
+            ),
+            nft_cell(
+                OWNER_LOCK_HASH,
+                build_nft_data(&derive_serial_id(&CLASS_TYPE_HASH, 4), &[], 0),
+            ),
+        ],
+    );
+    assert_eq!(validate_with(&chain), Ok(()));
+}
+
+#[test]
+fn test_generation_with_issued_class_serial_id() {
+    let args = build_args(
+        FLAG_CLASS | FLAG_ID_SCHEME,
+        &[&CLASS_TYPE_HASH, &[ID_SCHEME_CLASS_SERIAL]],
+    );
+    let chain = build_chain(
+        args,
+        vec![
+            cell(GOVERNANCE_LOCK_HASH, None, Vec::new()),
+            class_cell(3, 10),
+        ],
+        vec![
+            class_cell(4, 10),
+            nft_cell(
+                OWNER_LOCK_HASH,
+                build_nft_data(&derive_serial_id(&CLASS_TYPE_HASH, 2), &[], 0),
+            ),
+        ],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::InvalidNft));
+}
+
+#[test]
+fn test_unauthorized_burn() {
+    let chain = build_chain(
+        GOVERNANCE_LOCK_HASH.to_vec(),
+        vec![nft_cell(
+            OWNER_LOCK_HASH,
+            build_nft_data(&[7u8; 32], &[], 0),
+        )],
+        vec![cell(OWNER_LOCK_HASH, None, Vec::new())],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::UnauthorizedBurn));
+}
+
+#[test]
+fn test_burn_by_owner() {
+    let chain = build_chain(
+        build_args(FLAG_OWNER_BURN, &[]),
+        vec![nft_cell(
+            OWNER_LOCK_HASH,
+            build_nft_data(&[7u8; 32], &[], 0),
+        )],
+        vec![cell(OWNER_LOCK_HASH, None, Vec::new())],
+    );
+    assert_eq!(validate_with(&chain), Ok(()));
+}
+
+#[test]
+fn test_transfer_with_royalty() {
+    let data = build_nft_data(&[7u8; 32], &[], 0);
+    let royalty_lock_hash = [6u8; 32];
+    let args = build_args(FLAG_ROYALTY, &[&royalty_lock_hash, &1000u64.to_le_bytes()]);
+    let mut chain = build_chain(
+        args,
+        vec![nft_cell(OWNER_LOCK_HASH, data.clone())],
+        vec![nft_cell(BUYER_LOCK_HASH, data)],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::RoyaltyNotPaid));
+
+    chain
+        .outputs
+        .push(cell(royalty_lock_hash, None, Vec::new()));
+    assert_eq!(validate_with(&chain), Ok(()));
+}
+
+#[test]
+fn test_unknown_flags() {
+    let chain = build_chain(build_args(0x80, &[]), Vec::new(), Vec::new());
+    assert_eq!(validate_with(&chain), Err(Error::InvalidArgument));
+}
+
+#[test]
+fn test_class_cell_creation() {
+    let mut chain = build_class_cell_chain(
+        &[0u8; 32],
+        vec![cell(GOVERNANCE_LOCK_HASH, None, Vec::new())],
+        vec![class_cell(0, 10)],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::InvalidClassCell));
+
+    let class_id = derive_id(&chain.cell_inputs[0], 0);
+    chain.script_args = build_args(FLAG_CLASS_CELL, &[&class_id]);
+    assert_eq!(validate_with(&chain), Ok(()));
+
+    chain.inputs[0].lock_hash = OWNER_LOCK_HASH;
+    assert_eq!(validate_with(&chain), Err(Error::RequireGovernanceMode));
+}
+
+#[test]
+fn test_class_cell_creation_with_issued() {
+    let mut chain = build_class_cell_chain(
+        &[0u8; 32],
+        vec![cell(GOVERNANCE_LOCK_HASH, None, Vec::new())],
+        vec![class_cell(3, 10)],
+    );
+    let class_id = derive_id(&chain.cell_inputs[0], 0);
+    chain.script_args = build_args(FLAG_CLASS_CELL, &[&class_id]);
+    assert_eq!(validate_with(&chain), Err(Error::InvalidClassCell));
+}
+
+#[test]
+fn test_class_cell_update() {
+    let chain =
+        build_class_cell_chain(&[0u8; 32], vec![class_cell(3, 10)], vec![class_cell(5, 10)]);
+    assert_eq!(validate_with(&chain), Ok(()));
+}
+
+#[test]
+fn test_class_cell_update_exceeding_max_supply() {
+    let chain = build_class_cell_chain(
+        &[0u8; 32],
+        vec![class_cell(10, 10)],
+        vec![class_cell(11, 10)],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::SupplyCapExceeded));
+
+    // A max supply of 0 leaves the class uncapped
+    let chain =
+        build_class_cell_chain(&[0u8; 32], vec![class_cell(10, 0)], vec![class_cell(11, 0)]);
+    assert_eq!(validate_with(&chain), Ok(()));
+}
+
+#[test]
+fn test_class_cell_with_lowered_issued() {
+    let chain =
+        build_class_cell_chain(&[0u8; 32], vec![class_cell(3, 10)], vec![class_cell(2, 10)]);
+    assert_eq!(validate_with(&chain), Err(Error::InvalidClassCell));
+}
+
+#[test]
+fn test_class_cell_with_modified_max_supply() {
+    let chain =
+        build_class_cell_chain(&[0u8; 32], vec![class_cell(3, 10)], vec![class_cell(3, 20)]);
+    assert_eq!(validate_with(&chain), Err(Error::InvalidClassCell));
+}
+
+#[test]
+fn test_class_cell_destruction() {
+    let chain = build_class_cell_chain(
+        &[0u8; 32],
+        vec![class_cell(3, 10)],
+        vec![cell(GOVERNANCE_LOCK_HASH, None, Vec::new())],
+    );
+    assert_eq!(validate_with(&chain), Err(Error::InvalidClassCell));
+}
+
+#[test]
+fn test_class_cell_with_nft_sections() {
+    let args = build_args(FLAG_CLASS_CELL | FLAG_OWNER_BURN, &[&[0u8; 32]]);
+    let chain = build_chain(args, Vec::new(), Vec::new());
+    assert_eq!(validate_with(&chain), Err(Error::InvalidArgument));
+}
@@ -0,0 +1,20 @@
+//! Diagnostics for failing transactions. With the `debug-trace` feature
+//! enabled, `trace!` prints its message via `ckb_std::debug!`, which shows up
+//! in the debugger output of the test harness. Otherwise the arguments are
+//! only type checked, and the macro costs no cycles.
+
+#[cfg(feature = "debug-trace")]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        ckb_std::debug!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "debug-trace"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = core::format_args!($($arg)*);
+        }
+    };
+}
@@ -0,0 +1,440 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+// Import heap related library from `alloc`
+// https://doc.rust-lang.org/alloc/index.html
+use alloc::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    vec::Vec,
+};
+
+// Import CKB syscalls and structures
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use ckb_std::{ckb_constants::Source, ckb_types::prelude::*, high_level::QueryIter};
+use molecule::prelude::*;
+
+use crate::args::{RoyaltyConfig, ScriptArgs, SupplyConfig};
+use crate::chain::{ChainView, CkbChain};
+use crate::data::{
+    parse_nft_flags, parse_nft_id, parse_supply_counter, NFT_FLAG_FROZEN, NFT_FLAG_SOULBOUND,
+    SUPPLY_COUNTER_SIZE,
+};
+use crate::error::Error;
+use crate::id::{derive_id, derive_nft_id};
+use crate::schemas::nft::ClassDataReader;
+
+/// Validates the NFT cells of the running script group.
+pub fn validate() -> Result<(), Error> {
+    validate_with(&CkbChain)
+}
+
+/// Validates the NFT cells of the running script group, reading the
+/// transaction from `chain`.
+pub fn validate_with<C: ChainView>(chain: &C) -> Result<(), Error> {
+    // We will need to extract governance lock, as well as other optional
+    // settings from current running script.
+    let args = chain.load_script_args()?;
+    let args = ScriptArgs::parse(&args).map_err(|err| {
+        trace!("invalid script args: {:?}", err);
+        err
+    })?;
+
+    let input_lock_hashes: BTreeSet<[u8; 32]> = QueryIter::new(
+        |index, source| chain.load_cell_lock_hash(index, source),
+        Source::Input,
+    )
+    .collect();
+    let governance_mode = args.governance_mode(&input_lock_hashes);
+
+    // A class cell script runs its own rules, see `validate_class_cell`.
+    if let Some(class_id) = &args.class_cell_id {
+        return validate_class_cell(chain, class_id, governance_mode);
+    }
+
+    // To detect if an NFT is newly generated, we will need to first gather
+    // NFTs in input cells. When supply cap is enabled, the supply counter cell
+    // also uses current type script, it is told apart from NFT cells by the
+    // length of cell data.
+    let mut consumed_nfts: BTreeMap<[u8; 32], Vec<u8>> = BTreeMap::new();
+    let mut consumed_counters: Vec<u64> = Vec::new();
+    let mut duplicate_consumed = false;
+    let group_inputs = QueryIter::new(
+        |index, source| chain.load_cell_data(index, source),
+        Source::GroupInput,
+    );
+    for (index, data) in group_inputs.enumerate() {
+        if args.supply.is_some() && data.len() == SUPPLY_COUNTER_SIZE {
+            consumed_counters.push(parse_supply_counter(&data)?);
+        } else {
+            let nft_id = parse_nft_id(&data).map_err(|err| {
+                trace!("group input {}: {:?}", index, err);
+                err
+            })?;
+            // Output IDs are unique, so at most one consumed cell with the
+            // same ID survives, any extra one is burned.
+            if consumed_nfts.insert(nft_id, data).is_some() {
+                trace!("group input {}: duplicate NFT ID", index);
+                duplicate_consumed = true;
+            }
+        }
+    }
+
+    // In NFT generation, we will need to calculate a hash that includes the output
+    // index of the NFT cells. Let's first loop through all output cells to find
+    // the indices for all cells of the current type.
+    let output_indices = group_output_indices(chain)?;
+
+    // Now we can loop through each output NFT and validate them:
+    // 1. Each NFT ID can only appear once in the outputs, otherwise one consumed
+    // NFT might be transferred into multiple output cells.
+    // 2. If an NFT is found in consumed_nfts, this will be a transfer operation,
+    // unless we are in governance mode, the consumed NFT must not be frozen,
+    // and the NFT data, including metadata and flags, must be kept intact.
+    // Such transfers are counted, since each of them needs to pay royalty when
+    // the royalty section is enabled in script args.
+    // 3. If an NFT is not found in consumed_nfts, first, we need to ensure the
+    // script is in governance_mode, since NFT generation is only enabled in
+    // governance mode; second, the NFT ID must match the ID scheme selected in
+    // script args. Since the class serial scheme depends on the class cell,
+    // IDs of generated NFTs are verified after the class cell is validated.
+    let first_input = chain.load_input(0)?;
+    let mut output_nfts: BTreeSet<[u8; 32]> = BTreeSet::new();
+    let mut created_counters: Vec<(usize, u64)> = Vec::new();
+    let mut generated: Vec<(usize, [u8; 32])> = Vec::new();
+    let mut transferred = 0u64;
+    for index in output_indices {
+        let data = chain.load_cell_data(index, Source::Output)?;
+        if args.supply.is_some() && data.len() == SUPPLY_COUNTER_SIZE {
+            created_counters.push((index, parse_supply_counter(&data)?));
+            continue;
+        }
+        let nft_id = parse_nft_id(&data).map_err(|err| {
+            trace!("output {}: {:?}", index, err);
+            err
+        })?;
+        if !output_nfts.insert(nft_id) {
+            trace!("output {}: duplicate NFT ID", index);
+            return Err(Error::DuplicateNft);
+        }
+        if let Some(consumed_data) = consumed_nfts.get(&nft_id) {
+            if !governance_mode {
+                if parse_nft_flags(consumed_data) & (NFT_FLAG_FROZEN | NFT_FLAG_SOULBOUND) != 0 {
+                    trace!("output {}: NFT is frozen", index);
+                    return Err(Error::NftFrozen);
+                }
+                if consumed_data != &data {
+                    trace!("output {}: NFT data is modified", index);
+                    return Err(Error::ImmutableNftData);
+                }
+                transferred += 1;
+            }
+        } else {
+            if !governance_mode {
+                trace!("output {}: generation requires governance mode", index);
+                return Err(Error::RequireGovernanceMode);
+            }
+            generated.push((index, nft_id));
+        }
+    }
+    let minted = generated.len() as u64;
+
+    // Any consumed NFT that does not show up in the outputs is burned, as is
+    // any extra consumed cell sharing an ID with another one. Burning
+    // is only enabled in governance mode, unless script args allow owners to
+    // burn their own NFTs. In the latter case, the owner's lock script has
+    // already authorized consuming the NFT cell.
+    let burning = duplicate_consumed
+        || consumed_nfts
+            .keys()
+            .any(|nft_id| !output_nfts.contains(nft_id));
+    if burning && !governance_mode && !args.owner_burn_allowed() {
+        trace!("burning requires governance mode or owner burn permission");
+        return Err(Error::UnauthorizedBurn);
+    }
+
+    if let Some(supply) = &args.supply {
+        validate_supply(
+            supply,
+            &first_input,
+            &consumed_counters,
+            &created_counters,
+            minted,
+        )?;
+    }
+
+    let class_issued = match &args.class_type_hash {
+        Some(class_type_hash) => validate_class(chain, class_type_hash, minted)?,
+        None => 0,
+    };
+
+    // The class serial scheme numbers generated NFTs in output order, starting
+    // from the issued counter of the consumed class cell.
+    for (serial, (index, nft_id)) in (class_issued..).zip(generated.iter()) {
+        if derive_nft_id(args.id_scheme, &first_input, *index, serial) != *nft_id {
+            trace!("output {}: NFT ID does not match derived ID", index);
+            return Err(Error::InvalidNft);
+        }
+    }
+
+    if let Some(royalty) = &args.royalty {
+        validate_royalty(chain, royalty, transferred)?;
+    }
+
+    Ok(())
+}
+
+/// Indices in `Source::Output` of the output cells using current type script.
+fn group_output_indices<C: ChainView>(chain: &C) -> Result<Vec<usize>, Error> {
+    let script_hash = chain.load_script_hash()?;
+    let output_indices = QueryIter::new(
+        |index, source| match chain.load_cell_type_hash(index, source) {
+            Ok(Some(hash)) => Ok((Some(hash), index)),
+            Ok(None) => Ok((None, index)),
+            Err(err) => Err(err),
+        },
+        Source::Output,
+    )
+    .filter_map(|(current_script_hash, index)| {
+        if current_script_hash
+            .map(|s| s == script_hash)
+            .unwrap_or(false)
+        {
+            Some(index)
+        } else {
+            None
+        }
+    })
+    .collect();
+    Ok(output_indices)
+}
+
+/// The supply counter cell tracks the total number of NFTs ever generated.
+/// There can be at most one supply counter cell in inputs and outputs, each
+/// generated NFT must be accounted for in the counter, and the counter can
+/// never be destroyed. A counter can only be created once, in the
+/// transaction whose first input and output index derive the counter ID
+/// kept in script args.
+fn validate_supply(
+    supply: &SupplyConfig,
+    first_input: &[u8],
+    consumed_counters: &[u64],
+    created_counters: &[(usize, u64)],
+    minted: u64,
+) -> Result<(), Error> {
+    if consumed_counters.len() > 1 || created_counters.len() > 1 {
+        return Err(Error::InvalidSupplyCounter);
+    }
+    if supply.mint_limit > 0 && minted > supply.mint_limit {
+        trace!("minted {} exceeds mint limit {}", minted, supply.mint_limit);
+        return Err(Error::MintLimitExceeded);
+    }
+    let issued = match (consumed_counters.first(), created_counters.first()) {
+        (Some(previous), Some((_, issued))) => {
+            if previous.checked_add(minted) != Some(*issued) {
+                return Err(Error::InvalidSupplyCounter);
+            }
+            *issued
+        }
+        (None, Some((index, issued))) => {
+            if derive_id(first_input, *index) != supply.counter_id || *issued != minted {
+                return Err(Error::InvalidSupplyCounter);
+            }
+            *issued
+        }
+        (None, None) if minted == 0 => return Ok(()),
+        _ => return Err(Error::InvalidSupplyCounter),
+    };
+    if issued > supply.max_supply {
+        trace!("issued {} exceeds max supply {}", issued, supply.max_supply);
+        return Err(Error::SupplyCapExceeded);
+    }
+    Ok(())
+}
+
+/// The class cell, located by its type script hash, describes the collection
+/// NFTs belong to. NFT generation must consume and recreate the class cell,
+/// increasing its issued counter by the number of generated NFTs while
+/// keeping other fields intact. Other operations only need the class cell
+/// for reference, it can then be put in cell deps. The class cell itself is
+/// guarded by its own type script, which is expected to be nft-validator in
+/// class cell mode, see `validate_class_cell`.
+///
+/// Returns the issued counter of the consumed class cell, or 0 when no NFT
+/// is generated.
+fn validate_class<C: ChainView>(
+    chain: &C,
+    class_type_hash: &[u8; 32],
+    minted: u64,
+) -> Result<u64, Error> {
+    if minted == 0 {
+        return Ok(0);
+    }
+    let input_data = load_class_data(chain, class_type_hash, Source::Input)?;
+    let output_data = load_class_data(chain, class_type_hash, Source::Output)?;
+    let input_class =
+        ClassDataReader::from_slice(&input_data).map_err(|_| Error::MalformedClassData)?;
+    let output_class =
+        ClassDataReader::from_slice(&output_data).map_err(|_| Error::MalformedClassData)?;
+    if input_class.name().as_slice() != output_class.name().as_slice()
+        || input_class.description().as_slice() != output_class.description().as_slice()
+        || input_class.max_supply().as_slice() != output_class.max_supply().as_slice()
+    {
+        return Err(Error::InvalidClassCell);
+    }
+    let previous: u64 = input_class.issued().unpack();
+    let issued: u64 = output_class.issued().unpack();
+    if previous.checked_add(minted) != Some(issued) {
+        trace!(
+            "class issued {} does not match {} + {}",
+            issued,
+            previous,
+            minted
+        );
+        return Err(Error::InvalidClassCell);
+    }
+    let max_supply: u64 = output_class.max_supply().unpack();
+    if max_supply > 0 && issued > max_supply {
+        trace!("class issued {} exceeds max supply {}", issued, max_supply);
+        return Err(Error::SupplyCapExceeded);
+    }
+    Ok(previous)
+}
+
+/// In class cell mode, current type script governs the class cell of a
+/// collection, so that every transaction touching the class cell runs these
+/// rules, not only the ones generating NFTs. There can be at most one class
+/// cell in inputs and outputs. The class cell can only be created once, in
+/// governance mode, by the transaction whose first input and output index
+/// derive the class ID kept in script args, with an issued counter of 0.
+/// Once created, it can never be destroyed, its name, description and max
+/// supply are fixed, and its issued counter can never go down, which keeps
+/// class serial IDs unique, nor exceed the max supply when one is set.
+fn validate_class_cell<C: ChainView>(
+    chain: &C,
+    class_id: &[u8; 32],
+    governance_mode: bool,
+) -> Result<(), Error> {
+    let input_data: Vec<Vec<u8>> = QueryIter::new(
+        |index, source| chain.load_cell_data(index, source),
+        Source::GroupInput,
+    )
+    .collect();
+    let output_indices = group_output_indices(chain)?;
+    if input_data.len() > 1 || output_indices.len() > 1 {
+        trace!("more than one class cell");
+        return Err(Error::InvalidClassCell);
+    }
+    match (input_data.first(), output_indices.first()) {
+        (Some(input_data), Some(index)) => {
+            let output_data = chain.load_cell_data(*index, Source::Output)?;
+            let input_class =
+                ClassDataReader::from_slice(input_data).map_err(|_| Error::MalformedClassData)?;
+            let output_class =
+                ClassDataReader::from_slice(&output_data).map_err(|_| Error::MalformedClassData)?;
+            if input_class.name().as_slice() != output_class.name().as_slice()
+                || input_class.description().as_slice() != output_class.description().as_slice()
+                || input_class.max_supply().as_slice() != output_class.max_supply().as_slice()
+            {
+                trace!("output {}: class cell is modified", index);
+                return Err(Error::InvalidClassCell);
+            }
+            let previous: u64 = input_class.issued().unpack();
+            let issued: u64 = output_class.issued().unpack();
+            if issued < previous {
+                trace!(
+                    "output {}: class issued {} is less than {}",
+                    index,
+                    issued,
+                    previous
+                );
+                return Err(Error::InvalidClassCell);
+            }
+            let max_supply: u64 = output_class.max_supply().unpack();
+            if max_supply > 0 && issued > max_supply {
+                trace!(
+                    "output {}: class issued {} exceeds max supply {}",
+                    index,
+                    issued,
+                    max_supply
+                );
+                return Err(Error::SupplyCapExceeded);
+            }
+        }
+        (None, Some(index)) => {
+            let output_data = chain.load_cell_data(*index, Source::Output)?;
+            let output_class =
+                ClassDataReader::from_slice(&output_data).map_err(|_| Error::MalformedClassData)?;
+            if !governance_mode {
+                trace!("output {}: class creation requires governance mode", index);
+                return Err(Error::RequireGovernanceMode);
+            }
+            let first_input = chain.load_input(0)?;
+            if derive_id(&first_input, *index) != *class_id {
+                trace!("output {}: class ID does not match derived ID", index);
+                return Err(Error::InvalidClassCell);
+            }
+            let issued: u64 = output_class.issued().unpack();
+            if issued != 0 {
+                trace!("output {}: class is created with issued {}", index, issued);
+                return Err(Error::InvalidClassCell);
+            }
+        }
+        (Some(_), None) => {
+            trace!("class cell can not be destroyed");
+            return Err(Error::InvalidClassCell);
+        }
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+/// Loads the data of the only class cell in `source`.
+fn load_class_data<C: ChainView>(
+    chain: &C,
+    class_type_hash: &[u8; 32],
+    source: Source,
+) -> Result<Vec<u8>, Error> {
+    let mut indices = QueryIter::new(
+        |index, source| chain.load_cell_type_hash(index, source),
+        source,
+    )
+    .enumerate()
+    .filter(|(_, type_hash)| type_hash.as_ref() == Some(class_type_hash))
+    .map(|(index, _)| index);
+    match (indices.next(), indices.next()) {
+        (Some(index), None) => Ok(chain.load_cell_data(index, source)?),
+        _ => Err(Error::InvalidClassCell),
+    }
+}
+
+/// Each NFT transferred outside governance mode pays the configured royalty,
+/// the total capacity of output cells using the royalty lock must cover the
+/// royalties of all transferred NFTs.
+fn validate_royalty<C: ChainView>(
+    chain: &C,
+    royalty: &RoyaltyConfig,
+    transferred: u64,
+) -> Result<(), Error> {
+    if transferred == 0 {
+        return Ok(());
+    }
+    let required = royalty
+        .amount
+        .checked_mul(transferred)
+        .ok_or(Error::RoyaltyNotPaid)?;
+    let mut paid = 0u64;
+    let output_locks = QueryIter::new(
+        |index, source| chain.load_cell_lock_hash(index, source),
+        Source::Output,
+    );
+    for (index, lock_hash) in output_locks.enumerate() {
+        if lock_hash == royalty.lock_hash {
+            paid = paid.saturating_add(chain.load_cell_capacity(index, Source::Output)?);
+        }
+    }
+    if paid < required {
+        trace!("royalty paid {} is less than required {}", paid, required);
+        return Err(Error::RoyaltyNotPaid);
+    }
+    Ok(())
+}
@@ -1,10 +1,5 @@
-extern crate alloc;
-
-#[path = "../../contracts/nft-validator/src/validator.rs"]
-mod validator;
-
 fn main() {
-    if let Err(err) = validator::validate() {
+    if let Err(err) = nft_validator_core::validate() {
         std::process::exit(err as i32);
     }
 }
@@ -0,0 +1,172 @@
+use super::tests::{build_nft_data, derive_id, nft_cell, nft_scenario};
+use ckb_contract_test_harness::Cell;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{bytes::Bytes, packed::CellInput, prelude::*};
+use nft_validator_core::{data::NFT_DATA_MIN_SIZE, schemas::nft::NftData, Error};
+use proptest::{collection::vec, prelude::*};
+use std::collections::{HashMap, HashSet};
+
+const MAX_CYCLES: u64 = 10_000_000;
+
+/// How an NFT output cell of a generated transaction is built.
+#[derive(Clone, Debug)]
+enum NftOutput {
+    /// Transfers an input NFT intact.
+    Transfer(usize),
+    /// Transfers an input NFT with its metadata changed.
+    Modify(usize),
+    /// Generates an NFT with the ID derived from its output index.
+    Generate,
+    /// Generates an NFT with an ID that is not derived from the transaction.
+    WrongId,
+    /// Repeats the data of an earlier NFT output.
+    Duplicate(usize),
+    /// A generated NFT with data truncated to the given length.
+    Truncate(usize),
+}
+
+/// A generated NFT transaction: a funding input, followed by NFT inputs,
+/// then an optional plain output and the NFT outputs.
+#[derive(Clone, Debug)]
+struct NftTx {
+    governance: bool,
+    plain_output: bool,
+    inputs: usize,
+    outputs: Vec<NftOutput>,
+}
+
+fn nft_output() -> impl Strategy<Value = NftOutput> {
+    prop_oneof![
+        3 => (0..4usize).prop_map(NftOutput::Transfer),
+        1 => (0..4usize).prop_map(NftOutput::Modify),
+        3 => Just(NftOutput::Generate),
+        1 => Just(NftOutput::WrongId),
+        1 => (0..4usize).prop_map(NftOutput::Duplicate),
+        1 => (0..NFT_DATA_MIN_SIZE).prop_map(NftOutput::Truncate),
+    ]
+}
+
+fn nft_tx() -> impl Strategy<Value = NftTx> {
+    (
+        any::<bool>(),
+        any::<bool>(),
+        0..4usize,
+        vec(nft_output(), 0..5),
+    )
+        .prop_map(|(governance, plain_output, inputs, outputs)| NftTx {
+            governance,
+            plain_output,
+            inputs,
+            outputs,
+        })
+}
+
+fn nft_id(data: &Bytes) -> Bytes {
+    NftData::from_slice(data).expect("nft data").id().raw_data()
+}
+
+/// Model of the nft-validator rules for transactions without optional
+/// script args sections, `outputs` are the NFT outputs starting at `offset`.
+fn oracle(
+    governance: bool,
+    first_input: &CellInput,
+    offset: usize,
+    inputs: &[Bytes],
+    outputs: &[Bytes],
+) -> Result<(), Error> {
+    let consumed: HashMap<Bytes, &Bytes> = inputs.iter().map(|data| (nft_id(data), data)).collect();
+    let mut seen = HashSet::new();
+    let mut generated = Vec::new();
+    for (index, data) in outputs.iter().enumerate() {
+        if data.len() < NFT_DATA_MIN_SIZE {
+            return Err(Error::NftDataTooShort);
+        }
+        let id = nft_id(data);
+        if !seen.insert(id.clone()) {
+            return Err(Error::DuplicateNft);
+        }
+        match consumed.get(&id) {
+            Some(consumed_data) if !governance && *consumed_data != data => {
+                return Err(Error::ImmutableNftData)
+            }
+            Some(_) => {}
+            None if !governance => return Err(Error::RequireGovernanceMode),
+            None => generated.push((offset + index, id)),
+        }
+    }
+    if !governance && consumed.keys().any(|id| !seen.contains(id)) {
+        return Err(Error::UnauthorizedBurn);
+    }
+    for (index, id) in generated {
+        if derive_id(first_input, index as u64) != id {
+            return Err(Error::InvalidNft);
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_nft_rules_match_oracle(spec in nft_tx()) {
+        let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+        let owner = scenario.lock_script();
+        let funding_lock = if spec.governance { governance_script } else { owner.clone() };
+        scenario.input(Cell::new(&funding_lock, 100_000));
+        let inputs: Vec<Bytes> = (0..spec.inputs)
+            .map(|i| build_nft_data(&Bytes::from(vec![i as u8 + 1; 32]), &[]))
+            .collect();
+        for data in &inputs {
+            scenario.input(nft_cell(&owner, &nft_type_script, 1000, data.clone()));
+        }
+        let first_input = scenario.inputs()[0].clone();
+        let offset = if spec.plain_output {
+            scenario.output(Cell::new(&funding_lock, 50_000));
+            1
+        } else {
+            0
+        };
+
+        let mut outputs: Vec<Bytes> = Vec::new();
+        for (k, output) in spec.outputs.iter().enumerate() {
+            let generated = build_nft_data(&derive_id(&first_input, (offset + k) as u64), &[]);
+            let data = match *output {
+                NftOutput::Transfer(i) if !inputs.is_empty() => inputs[i % inputs.len()].clone(),
+                NftOutput::Modify(i) if !inputs.is_empty() => {
+                    build_nft_data(&nft_id(&inputs[i % inputs.len()]), b"modified")
+                }
+                NftOutput::Duplicate(j) if !outputs.is_empty() => {
+                    outputs[j % outputs.len()].clone()
+                }
+                NftOutput::WrongId => build_nft_data(&Bytes::from(vec![0xff; 32]), &[]),
+                NftOutput::Truncate(len) => generated.slice(0..len),
+                _ => generated,
+            };
+            outputs.push(data.clone());
+            scenario.output(nft_cell(&owner, &nft_type_script, 1000, data));
+        }
+
+        let expected = oracle(spec.governance, &first_input, offset, &inputs, &outputs);
+        let tx = scenario.build();
+        let result = scenario.context().verify_tx(&tx, MAX_CYCLES);
+        match (expected, result) {
+            (Ok(()), Ok(_)) => {}
+            (Err(expected), Err(err)) => {
+                let code = ScriptError::ValidationFailure(expected as i8).to_string();
+                prop_assert!(
+                    err.to_string().contains(&code),
+                    "expected {}, got {}",
+                    code,
+                    err
+                );
+            }
+            (expected, result) => {
+                return Err(TestCaseError::fail(format!(
+                    "oracle: {:?}, ckb-vm: {:?}",
+                    expected, result
+                )));
+            }
+        }
+    }
+}
@@ -1,539 +1,1304 @@
-use super::*;
 use blake2b_rs::Blake2bBuilder;
-use ckb_standalone_debugger::transaction::{
-    MockCellDep, MockInfo, MockInput, MockTransaction, ReprMockTransaction,
+use ckb_contract_test_harness::{
+    build_mock_transaction, check_cycles, native::dep_group_members, random_32bytes, Cell,
+    Mutation, TxScenario,
 };
-use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::{assert_error_eq, Error};
+use ckb_tool::ckb_script::ScriptError;
 use ckb_tool::ckb_types::{
     bytes::Bytes,
-    core::{DepType, TransactionBuilder, TransactionView},
+    core::{DepType, EpochNumberWithFraction, HeaderBuilder},
     packed::*,
     prelude::*,
 };
-use ckb_x64_simulator::RunningSetup;
-use rand::{thread_rng, Rng};
-use serde_json::to_string_pretty;
-use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
-
-pub fn random_32bytes() -> Bytes {
-    let mut rng = thread_rng();
-    let mut buf = vec![0u8; 32];
-    rng.fill(&mut buf[..]);
-    Bytes::from(buf)
-}
-
-pub fn create_test_folder(name: &str) -> PathBuf {
-    let mut path = TX_FOLDER.clone();
-    path.push(&name);
-    fs::create_dir_all(&path).expect("create folder");
-    path
-}
-
-pub fn build_mock_transaction(tx: &TransactionView, context: &Context) -> MockTransaction {
-    let mock_inputs = tx
-        .inputs()
-        .into_iter()
-        .map(|input| {
-            let (output, data) = context
-                .get_cell(&input.previous_output())
-                .expect("get cell");
-            MockInput {
-                input,
-                output,
-                data,
-                header: None,
-            }
-        })
-        .collect();
-    let mock_cell_deps = tx
-        .cell_deps()
-        .into_iter()
-        .map(|cell_dep| {
-            if cell_dep.dep_type() == DepType::DepGroup.into() {
-                panic!("Implement dep group support later!");
-            }
-            let (output, data) = context.get_cell(&cell_dep.out_point()).expect("get cell");
-            MockCellDep {
-                cell_dep,
-                output,
-                data,
-                header: None,
-            }
-        })
-        .collect();
-    let mock_info = MockInfo {
-        inputs: mock_inputs,
-        cell_deps: mock_cell_deps,
-        header_deps: vec![],
-    };
-    MockTransaction {
-        mock_info,
-        tx: tx.data(),
+use molecule::prelude::Byte;
+use nft_validator_core::{
+    args::{
+        FLAG_CLASS, FLAG_CLASS_CELL, FLAG_ID_SCHEME, FLAG_MULTI_GOVERNANCE, FLAG_OWNER_BURN,
+        FLAG_ROYALTY, FLAG_SUPPLY_CAP, ID_SCHEME_CLASS_SERIAL,
+    },
+    data::{NFT_FLAG_FROZEN, NFT_FLAG_SOULBOUND},
+    schemas::{
+        blockchain as schema,
+        nft::{ClassData, NftData},
+    },
+};
+
+pub fn ckb_hash(data: &[u8]) -> Bytes {
+    let mut blake2b = Blake2bBuilder::new(32)
+        .personal(b"ckb-default-hash")
+        .build();
+    blake2b.update(data);
+    let mut hash = vec![0u8; 32];
+    blake2b.finalize(&mut hash[..]);
+    Bytes::from(hash)
+}
+
+pub fn derive_id(first_input: &CellInput, index: u64) -> Bytes {
+    ckb_hash(&[first_input.as_slice(), &index.to_le_bytes()].concat())
+}
+
+// The schemas of nft-validator-core are built on the ckb-std types, which
+// are separate from the ckb-tool ones used by the tests
+fn schema_byte32(data: &[u8]) -> schema::Byte32 {
+    schema::Byte32::from_slice(data).expect("byte32")
+}
+
+fn schema_bytes(data: &[u8]) -> schema::Bytes {
+    schema::Bytes::new_builder()
+        .set(data.iter().map(|b| Byte::new(*b)).collect())
+        .build()
+}
+
+fn schema_uint64(value: u64) -> schema::Uint64 {
+    schema::Uint64::from_slice(&value.to_le_bytes()).expect("uint64")
+}
+
+pub fn build_nft_data(nft_id: &Bytes, metadata: &[u8]) -> Bytes {
+    build_nft_data_with_flags(nft_id, metadata, 0)
+}
+
+pub fn build_nft_data_with_flags(nft_id: &Bytes, metadata: &[u8], flags: u8) -> Bytes {
+    NftData::new_builder()
+        .id(schema_byte32(nft_id))
+        .content_hash(schema_byte32(&ckb_hash(metadata)))
+        .metadata(schema_bytes(metadata))
+        .flags(Byte::new(flags))
+        .build()
+        .as_bytes()
+}
+
+pub fn build_supply_args(
+    governance_lock_hash: &Byte32,
+    max_supply: u64,
+    mint_limit: u64,
+    counter_id: &Bytes,
+) -> Bytes {
+    let mut args = governance_lock_hash.as_slice().to_vec();
+    args.push(FLAG_SUPPLY_CAP);
+    args.extend_from_slice(&max_supply.to_le_bytes());
+    args.extend_from_slice(&mint_limit.to_le_bytes());
+    args.extend_from_slice(counter_id);
+    Bytes::from(args)
+}
+
+pub fn build_multi_governance_args(governance_lock_hashes: &[Byte32], threshold: u8) -> Bytes {
+    let mut args = governance_lock_hashes[0].as_slice().to_vec();
+    args.push(FLAG_MULTI_GOVERNANCE);
+    args.push(threshold);
+    args.push((governance_lock_hashes.len() - 1) as u8);
+    for lock_hash in &governance_lock_hashes[1..] {
+        args.extend_from_slice(lock_hash.as_slice());
     }
+    Bytes::from(args)
 }
 
-pub fn write_native_setup(
-    test_name: &str,
-    binary_name: &str,
-    tx: &TransactionView,
-    context: &Context,
-    setup: &RunningSetup,
-) {
-    let folder = create_test_folder(test_name);
-    let mock_tx = build_mock_transaction(&tx, &context);
-    let repr_tx: ReprMockTransaction = mock_tx.into();
-    let tx_json = to_string_pretty(&repr_tx).expect("serialize to json");
-    fs::write(folder.join("tx.json"), tx_json).expect("write tx to local file");
-    let setup_json = to_string_pretty(setup).expect("serialize to json");
-    fs::write(folder.join("setup.json"), setup_json).expect("write setup to local file");
-    fs::write(
-        folder.join("cmd"),
-        format!(
-            "CKB_TX_FILE=\"{}\" CKB_RUNNING_SETUP=\"{}\" \"{}\"",
-            folder.join("tx.json").to_str().expect("utf8"),
-            folder.join("setup.json").to_str().expect("utf8"),
-            Loader::default().path(binary_name).to_str().expect("utf8")
-        ),
-    )
-    .expect("write cmd to local file");
+pub fn build_royalty_args(
+    governance_lock_hash: &Byte32,
+    royalty_lock_hash: &Byte32,
+    amount: u64,
+) -> Bytes {
+    let mut args = governance_lock_hash.as_slice().to_vec();
+    args.push(FLAG_ROYALTY);
+    args.extend_from_slice(royalty_lock_hash.as_slice());
+    args.extend_from_slice(&amount.to_le_bytes());
+    Bytes::from(args)
+}
+
+pub fn build_class_args(governance_lock_hash: &Byte32, class_type_hash: &Byte32) -> Bytes {
+    let mut args = governance_lock_hash.as_slice().to_vec();
+    args.push(FLAG_CLASS);
+    args.extend_from_slice(class_type_hash.as_slice());
+    Bytes::from(args)
+}
+
+pub fn build_class_serial_args(governance_lock_hash: &Byte32, class_type_hash: &Byte32) -> Bytes {
+    let mut args = governance_lock_hash.as_slice().to_vec();
+    args.push(FLAG_CLASS | FLAG_ID_SCHEME);
+    args.extend_from_slice(class_type_hash.as_slice());
+    args.push(ID_SCHEME_CLASS_SERIAL);
+    Bytes::from(args)
+}
+
+pub fn build_class_cell_args(governance_lock_hash: &Byte32, class_id: &Bytes) -> Bytes {
+    let mut args = governance_lock_hash.as_slice().to_vec();
+    args.push(FLAG_CLASS_CELL);
+    args.extend_from_slice(class_id);
+    Bytes::from(args)
+}
+
+pub fn derive_serial_id(class_type_hash: &Byte32, serial: u64) -> Bytes {
+    ckb_hash(&[class_type_hash.as_slice(), &serial.to_le_bytes()].concat())
+}
+
+pub fn build_class_data(name: &[u8], issued: u64, max_supply: u64) -> Bytes {
+    ClassData::new_builder()
+        .name(schema_bytes(name))
+        .description(schema_bytes(b"test collection"))
+        .issued(schema_uint64(issued))
+        .max_supply(schema_uint64(max_supply))
+        .build()
+        .as_bytes()
+}
+
+pub fn supply_counter_data(issued: u64) -> Bytes {
+    Bytes::from(issued.to_le_bytes().to_vec())
+}
+
+/// Deploys nft-validator and returns the scenario, the governance lock and an
+/// nft type script governed by it.
+pub fn nft_scenario() -> (TxScenario, Script, Script) {
+    let mut scenario = TxScenario::new();
+    scenario.deploy("nft-validator");
+    let governance_script = scenario.lock_script();
+    let nft_type_script = scenario.script(
+        "nft-validator",
+        governance_script.calc_script_hash().raw_data(),
+    );
+    (scenario, governance_script, nft_type_script)
+}
+
+pub fn nft_cell(
+    lock_script: &Script,
+    nft_type_script: &Script,
+    capacity: u64,
+    data: Bytes,
+) -> Cell {
+    Cell::new(lock_script, capacity)
+        .type_(nft_type_script)
+        .data(data)
 }
 
 const MAX_CYCLES: u64 = 10_000_000;
 
+// Exit codes of nft-validator, see `Error` in nft-validator-core
+const ERROR_INDEX_OUT_OF_BOUND: i8 = 1;
+const ERROR_INVALID_ARGUMENT: i8 = 5;
+const ERROR_REQUIRE_GOVERNANCE_MODE: i8 = 6;
+const ERROR_INVALID_NFT: i8 = 7;
+const ERROR_UNAUTHORIZED_BURN: i8 = 8;
+const ERROR_DUPLICATE_NFT: i8 = 9;
+const ERROR_IMMUTABLE_NFT_DATA: i8 = 10;
+const ERROR_INVALID_SUPPLY_COUNTER: i8 = 11;
+const ERROR_SUPPLY_CAP_EXCEEDED: i8 = 12;
+const ERROR_MINT_LIMIT_EXCEEDED: i8 = 13;
+const ERROR_NFT_FROZEN: i8 = 14;
+const ERROR_ROYALTY_NOT_PAID: i8 = 15;
+const ERROR_INVALID_CLASS_CELL: i8 = 16;
+const ERROR_NFT_DATA_TOO_SHORT: i8 = 18;
+const ERROR_MALFORMED_NFT_DATA: i8 = 19;
+
 #[test]
 fn test_nft_transfer() {
-    // deploy contract
-    let mut context = Context::default();
-    let nft_bin: Bytes = Loader::default().load_binary("nft-validator");
-    let nft_out_point = context.deploy_cell(nft_bin);
-    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
-
-    // prepare scripts
-    let lock_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let lock_script_dep = CellDep::new_builder()
-        .out_point(always_success_out_point.clone())
-        .build();
-    let lock_script2 = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let governance_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let governance_script_hash = governance_script.calc_script_hash();
-    let nft_type_script = context
-        .build_script(&nft_out_point, governance_script_hash.raw_data())
-        .expect("script");
-    let nft_script_dep = CellDep::new_builder()
-        .out_point(nft_out_point.clone())
-        .build();
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            nft_data.clone(),
+        ))
+        .output(nft_cell(&lock_script2, &nft_type_script, 999, nft_data))
+        .run_input_type(0);
 
-    // prepare cells
-    let nft_id = random_32bytes();
-    let input_out_point = context.create_cell(
-        CellOutput::new_builder()
-            .capacity(1000u64.pack())
-            .lock(lock_script.clone())
-            .type_(
-                ScriptOpt::new_builder()
-                    .set(Some(nft_type_script.clone()))
-                    .build(),
-            )
-            .build(),
-        nft_id.clone(),
-    );
-    let input = CellInput::new_builder()
-        .previous_output(input_out_point)
-        .build();
-    let outputs = vec![CellOutput::new_builder()
-        .capacity(999u64.pack())
-        .lock(lock_script2.clone())
-        .type_(
-            ScriptOpt::new_builder()
-                .set(Some(nft_type_script.clone()))
-                .build(),
-        )
-        .build()];
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_transfer", cycles);
 
-    let outputs_data = vec![nft_id];
+    // dump raw test tx files
+    scenario.dump("nft_transfer", "nft-validator-sim", 0);
 
-    // build transaction
-    let tx = TransactionBuilder::default()
-        .input(input)
-        .outputs(outputs)
-        .outputs_data(outputs_data.pack())
-        .cell_dep(lock_script_dep)
-        .cell_dep(nft_script_dep)
+    // reject mutants of the transaction, flipping the last byte of NFT data
+    // sets unknown NFT flags
+    scenario.check_mutants(
+        "nft_transfer",
+        "nft-validator-sim",
+        MAX_CYCLES,
+        |mutation| match mutation {
+            Mutation::DropInput(_) => ERROR_INDEX_OUT_OF_BOUND,
+            Mutation::FlipOutputData(_) => ERROR_MALFORMED_NFT_DATA,
+            Mutation::TruncateArgs => ERROR_INVALID_ARGUMENT,
+            mutation => panic!("unexpected mutation {}", mutation),
+        },
+    );
+}
+
+#[test]
+fn test_nft_transfer_through_dep_group() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            nft_data.clone(),
+        ))
+        .output(nft_cell(&lock_script2, &nft_type_script, 999, nft_data))
+        .dep_group()
+        .run_input_type(0);
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_transfer_through_dep_group", cycles);
+
+    // the dumped transaction lists the group members at the positions CKB-VM
+    // resolves them to, followed by the dep group cell itself
+    let tx = scenario.build();
+    let dep_group = tx.cell_deps().get(0).expect("dep group");
+    let (_, dep_group_data) = scenario
+        .context()
+        .get_cell(&dep_group.out_point())
+        .expect("get dep group");
+    let expected: Vec<Bytes> = dep_group_members(&dep_group_data)
+        .into_iter()
+        .map(|out_point| {
+            CellDep::new_builder()
+                .out_point(out_point)
+                .dep_type(DepType::Code.into())
+                .build()
+        })
+        .chain(Some(dep_group))
+        .map(|cell_dep| cell_dep.as_bytes())
+        .collect();
+    let mock_tx = build_mock_transaction(&tx, scenario.context());
+    let cell_deps: Vec<Bytes> = mock_tx
+        .mock_info
+        .cell_deps
+        .iter()
+        .map(|mock_cell_dep| mock_cell_dep.cell_dep.as_bytes())
+        .collect();
+    assert_eq!(cell_deps, expected);
+
+    // dump raw test tx files
+    scenario.dump("nft_transfer_through_dep_group", "nft-validator-sim", 0);
+}
+
+#[test]
+fn test_nft_transfer_with_header_deps() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario.input(nft_cell(
+        &lock_script,
+        &nft_type_script,
+        1000,
+        nft_data.clone(),
+    ));
+    // commit the input in a block, whose header is also a header dep
+    let header = HeaderBuilder::default()
+        .number(100u64.pack())
+        .epoch(EpochNumberWithFraction::new(2, 50, 1800).pack())
+        .timestamp(1_600_000_000_000u64.pack())
         .build();
-    let tx = context.complete_tx(tx);
+    let input_out_point = scenario.inputs()[0].previous_output();
+    scenario.context_mut().insert_header(header.clone());
+    scenario
+        .context_mut()
+        .link_cell_with_block(input_out_point, header.hash(), 0);
+    scenario
+        .output(nft_cell(&lock_script2, &nft_type_script, 999, nft_data))
+        .header_dep(header.hash())
+        .run_input_type(0);
 
     // run
-    let cycles = context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect("pass verification");
-    println!("consume cycles: {}", cycles);
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_transfer_with_header_deps", cycles);
+
+    // the dumped transaction carries the header dep and the input's block
+    let mock_tx = build_mock_transaction(&scenario.build(), scenario.context());
+    assert!(mock_tx
+        .mock_info
+        .header_deps
+        .iter()
+        .any(|header_dep| header_dep.hash() == header.hash()));
+    assert_eq!(mock_tx.mock_info.inputs[0].header, Some(header.hash()));
 
     // dump raw test tx files
-    let setup = RunningSetup {
-        is_lock_script: false,
-        is_output: false,
-        script_index: 0,
-        native_binaries: HashMap::default(),
-    };
-    write_native_setup("nft_transfer", "nft-validator-sim", &tx, &context, &setup);
+    scenario.dump("nft_transfer_with_header_deps", "nft-validator-sim", 0);
 }
 
 #[test]
 fn test_nft_generation() {
-    // deploy contract
-    let mut context = Context::default();
-    let nft_bin: Bytes = Loader::default().load_binary("nft-validator");
-    let nft_out_point = context.deploy_cell(nft_bin);
-    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
-
-    // prepare scripts
-    let lock_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let lock_script_dep = CellDep::new_builder()
-        .out_point(always_success_out_point.clone())
-        .build();
-    let governance_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let governance_script_hash = governance_script.calc_script_hash();
-    let nft_type_script = context
-        .build_script(&nft_out_point, governance_script_hash.raw_data())
-        .expect("script");
-    let nft_script_dep = CellDep::new_builder()
-        .out_point(nft_out_point.clone())
-        .build();
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&governance_script, 10000));
+    let nft_id = derive_id(&scenario.inputs()[0], 1);
+    scenario
+        .output(Cell::new(&governance_script, 9500))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&nft_id, &[]),
+        ))
+        .run_output_type(1);
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_generation", cycles);
+
+    // dump raw test tx files
+    scenario.dump("nft_generation", "nft-validator-sim", 0);
 
-    // prepare cells
-    let input_out_point = context.create_cell(
-        CellOutput::new_builder()
-            .capacity(10000u64.pack())
-            .lock(governance_script.clone())
-            .build(),
-        Bytes::new(),
+    // reject mutants of the transaction, dropping the governance input leaves
+    // no first input to derive NFT IDs from, and flipping the last byte of
+    // NFT data sets unknown NFT flags
+    scenario.check_mutants(
+        "nft_generation",
+        "nft-validator-sim",
+        MAX_CYCLES,
+        |mutation| match mutation {
+            Mutation::DropInput(_) => ERROR_INDEX_OUT_OF_BOUND,
+            Mutation::FlipOutputData(_) => ERROR_MALFORMED_NFT_DATA,
+            Mutation::SwapOutputs(_, _) => ERROR_INVALID_NFT,
+            Mutation::TruncateArgs => ERROR_INVALID_ARGUMENT,
+            mutation => panic!("unexpected mutation {}", mutation),
+        },
     );
-    let input = CellInput::new_builder()
-        .previous_output(input_out_point)
-        .build();
+}
 
-    let mut blake2b = Blake2bBuilder::new(32)
-        .personal(b"ckb-default-hash")
-        .build();
-    blake2b.update(input.as_slice());
-    blake2b.update(&1u64.to_le_bytes());
-    let mut hash = vec![0u8; 32];
-    blake2b.finalize(&mut hash[..]);
-    let nft_id = Bytes::from(hash);
-
-    let outputs = vec![
-        CellOutput::new_builder()
-            .capacity(9500u64.pack())
-            .lock(governance_script.clone())
-            .build(),
-        CellOutput::new_builder()
-            .capacity(499u64.pack())
-            .lock(lock_script.clone())
-            .type_(
-                ScriptOpt::new_builder()
-                    .set(Some(nft_type_script.clone()))
-                    .build(),
-            )
-            .build(),
-    ];
-
-    let outputs_data = vec![Bytes::new(), nft_id];
-
-    // build transaction
-    let tx = TransactionBuilder::default()
-        .input(input)
-        .outputs(outputs)
-        .outputs_data(outputs_data.pack())
-        .cell_dep(lock_script_dep)
-        .cell_dep(nft_script_dep)
-        .build();
-    let tx = context.complete_tx(tx);
+#[test]
+fn test_nft_invalid_governance() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&lock_script, 10000));
+    let nft_id = derive_id(&scenario.inputs()[0], 1);
+    scenario
+        .output(Cell::new(&lock_script, 9500))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&nft_id, &[]),
+        ))
+        .run_output_type(1);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_REQUIRE_GOVERNANCE_MODE).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_invalid_governance_failure",
+        "nft-validator-sim",
+        ERROR_REQUIRE_GOVERNANCE_MODE,
+    );
+}
+
+#[test]
+fn test_nft_invalid_nft_data() {
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&governance_script, 10000));
+    let nft_id = derive_id(&scenario.inputs()[0], 1);
+    scenario
+        .output(Cell::new(&governance_script, 9500))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            nft_id.slice(0..16),
+        ))
+        .run_output_type(1);
 
     // run
-    let cycles = context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect("pass verification");
-    println!("consume cycles: {}", cycles);
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_NFT_DATA_TOO_SHORT).output_type_script(1)
+    );
 
     // dump raw test tx files
-    let setup = RunningSetup {
-        is_lock_script: false,
-        is_output: true,
-        script_index: 1,
-        native_binaries: HashMap::default(),
-    };
-    write_native_setup("nft_generation", "nft-validator-sim", &tx, &context, &setup);
+    scenario.dump(
+        "nft_invalid_nft_data_failure",
+        "nft-validator-sim",
+        ERROR_NFT_DATA_TOO_SHORT,
+    );
 }
 
 #[test]
-fn test_nft_invalid_governance() {
-    // deploy contract
-    let mut context = Context::default();
-    let nft_bin: Bytes = Loader::default().load_binary("nft-validator");
-    let nft_out_point = context.deploy_cell(nft_bin);
-    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
-
-    // prepare scripts
-    let lock_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let lock_script_dep = CellDep::new_builder()
-        .out_point(always_success_out_point.clone())
-        .build();
-    let nft_type_script = context
-        .build_script(&nft_out_point, random_32bytes())
-        .expect("script");
-    let nft_script_dep = CellDep::new_builder()
-        .out_point(nft_out_point.clone())
-        .build();
+fn test_nft_malformed_nft_data() {
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&governance_script, 10000));
+    let nft_id = derive_id(&scenario.inputs()[0], 1);
+    scenario
+        .output(Cell::new(&governance_script, 9500))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            Bytes::from([nft_id.to_vec(), vec![0u8; 96]].concat()),
+        ))
+        .run_output_type(1);
 
-    // prepare cells
-    let input_out_point = context.create_cell(
-        CellOutput::new_builder()
-            .capacity(10000u64.pack())
-            .lock(lock_script.clone())
-            .build(),
-        Bytes::new(),
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_MALFORMED_NFT_DATA).output_type_script(1)
     );
-    let input = CellInput::new_builder()
-        .previous_output(input_out_point)
-        .build();
 
-    let mut blake2b = Blake2bBuilder::new(32)
-        .personal(b"ckb-default-hash")
-        .build();
-    blake2b.update(input.as_slice());
-    blake2b.update(&1u64.to_le_bytes());
-    let mut hash = vec![0u8; 32];
-    blake2b.finalize(&mut hash[..]);
-    let nft_id = Bytes::from(hash);
-
-    let outputs = vec![
-        CellOutput::new_builder()
-            .capacity(9500u64.pack())
-            .lock(lock_script.clone())
-            .build(),
-        CellOutput::new_builder()
-            .capacity(499u64.pack())
-            .lock(lock_script.clone())
-            .type_(
-                ScriptOpt::new_builder()
-                    .set(Some(nft_type_script.clone()))
-                    .build(),
-            )
-            .build(),
-    ];
-
-    let outputs_data = vec![Bytes::new(), nft_id];
-
-    // build transaction
-    let tx = TransactionBuilder::default()
-        .input(input)
-        .outputs(outputs)
-        .outputs_data(outputs_data.pack())
-        .cell_dep(lock_script_dep)
-        .cell_dep(nft_script_dep)
-        .build();
-    let tx = context.complete_tx(tx);
+    // dump raw test tx files
+    scenario.dump(
+        "nft_malformed_nft_data_failure",
+        "nft-validator-sim",
+        ERROR_MALFORMED_NFT_DATA,
+    );
+}
+
+#[test]
+fn test_nft_invalid_nft_hash() {
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&governance_script, 10000));
+    let mut nft_id = derive_id(&scenario.inputs()[0], 1).to_vec();
+    nft_id[0] += 1;
+    scenario
+        .output(Cell::new(&governance_script, 9500))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&Bytes::from(nft_id), &[]),
+        ))
+        .run_output_type(1);
 
     // run
-    assert!(context.verify_tx(&tx, MAX_CYCLES).is_err());
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_NFT).output_type_script(1)
+    );
 
     // dump raw test tx files
-    let setup = RunningSetup {
-        is_lock_script: false,
-        is_output: true,
-        script_index: 1,
-        native_binaries: HashMap::default(),
-    };
-    write_native_setup("nft_invalid_governance_failure", "nft-validator-sim", &tx, &context, &setup);
+    scenario.dump(
+        "nft_invalid_nft_hash_failure",
+        "nft-validator-sim",
+        ERROR_INVALID_NFT,
+    );
 }
 
 #[test]
-fn test_nft_invalid_nft_data() {
-    // deploy contract
-    let mut context = Context::default();
-    let nft_bin: Bytes = Loader::default().load_binary("nft-validator");
-    let nft_out_point = context.deploy_cell(nft_bin);
-    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
-
-    // prepare scripts
-    let lock_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let lock_script_dep = CellDep::new_builder()
-        .out_point(always_success_out_point.clone())
-        .build();
-    let governance_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let governance_script_hash = governance_script.calc_script_hash();
-    let nft_type_script = context
-        .build_script(&nft_out_point, governance_script_hash.raw_data())
-        .expect("script");
-    let nft_script_dep = CellDep::new_builder()
-        .out_point(nft_out_point.clone())
-        .build();
+fn test_nft_burn_in_governance_mode() {
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario
+        .input(nft_cell(&lock_script, &nft_type_script, 1000, nft_data))
+        .input(Cell::new(&governance_script, 10000))
+        .output(Cell::new(&governance_script, 10999))
+        .run_input_type(0);
 
-    // prepare cells
-    let input_out_point = context.create_cell(
-        CellOutput::new_builder()
-            .capacity(10000u64.pack())
-            .lock(governance_script.clone())
-            .build(),
-        Bytes::new(),
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_burn_in_governance_mode", cycles);
+
+    // dump raw test tx files
+    scenario.dump("nft_burn_in_governance_mode", "nft-validator-sim", 0);
+}
+
+#[test]
+fn test_nft_burn_by_owner() {
+    let (mut scenario, governance_script, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    // Allow owners to burn NFTs via the flags byte
+    let nft_args = Bytes::from(
+        [
+            governance_script.calc_script_hash().as_slice(),
+            &[FLAG_OWNER_BURN],
+        ]
+        .concat(),
     );
-    let input = CellInput::new_builder()
-        .previous_output(input_out_point)
-        .build();
+    let nft_type_script = scenario.script("nft-validator", nft_args);
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario
+        .input(nft_cell(&lock_script, &nft_type_script, 1000, nft_data))
+        .output(Cell::new(&lock_script, 999))
+        .run_input_type(0);
 
-    let mut blake2b = Blake2bBuilder::new(32)
-        .personal(b"ckb-default-hash")
-        .build();
-    blake2b.update(input.as_slice());
-    blake2b.update(&1u64.to_le_bytes());
-    let mut hash = vec![0u8; 32];
-    blake2b.finalize(&mut hash[..]);
-    let nft_id = Bytes::from(hash);
-
-    let outputs = vec![
-        CellOutput::new_builder()
-            .capacity(9500u64.pack())
-            .lock(governance_script.clone())
-            .build(),
-        CellOutput::new_builder()
-            .capacity(499u64.pack())
-            .lock(lock_script.clone())
-            .type_(
-                ScriptOpt::new_builder()
-                    .set(Some(nft_type_script.clone()))
-                    .build(),
-            )
-            .build(),
-    ];
-
-    let outputs_data = vec![Bytes::new(), nft_id.slice(0..16)];
-
-    // build transaction
-    let tx = TransactionBuilder::default()
-        .input(input)
-        .outputs(outputs)
-        .outputs_data(outputs_data.pack())
-        .cell_dep(lock_script_dep)
-        .cell_dep(nft_script_dep)
-        .build();
-    let tx = context.complete_tx(tx);
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_burn_by_owner", cycles);
+
+    // dump raw test tx files
+    scenario.dump("nft_burn_by_owner", "nft-validator-sim", 0);
+}
+
+#[test]
+fn test_nft_unauthorized_burn() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario
+        .input(nft_cell(&lock_script, &nft_type_script, 1000, nft_data))
+        .output(Cell::new(&lock_script, 999))
+        .run_input_type(0);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_UNAUTHORIZED_BURN).input_type_script(0)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_unauthorized_burn_failure",
+        "nft-validator-sim",
+        ERROR_UNAUTHORIZED_BURN,
+    );
+}
+
+#[test]
+fn test_nft_duplicate_transfer() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            nft_data.clone(),
+        ))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            nft_data.clone(),
+        ))
+        .output(nft_cell(&lock_script2, &nft_type_script, 500, nft_data))
+        .run_input_type(0);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_DUPLICATE_NFT).input_type_script(0)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_duplicate_transfer_failure",
+        "nft-validator-sim",
+        ERROR_DUPLICATE_NFT,
+    );
+}
+
+#[test]
+fn test_nft_duplicate_generation() {
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&governance_script, 10000));
+    let nft_data = build_nft_data(&derive_id(&scenario.inputs()[0], 1), &[]);
+    scenario
+        .output(Cell::new(&governance_script, 9000))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            nft_data.clone(),
+        ))
+        .output(nft_cell(&lock_script, &nft_type_script, 499, nft_data))
+        .run_output_type(1);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_DUPLICATE_NFT).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_duplicate_generation_failure",
+        "nft-validator-sim",
+        ERROR_DUPLICATE_NFT,
+    );
+}
+
+#[test]
+fn test_nft_transfer_with_modified_metadata() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_id = random_32bytes();
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            build_nft_data(&nft_id, b"original metadata"),
+        ))
+        .output(nft_cell(
+            &lock_script2,
+            &nft_type_script,
+            999,
+            build_nft_data(&nft_id, b"modified metadata"),
+        ))
+        .run_input_type(0);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_IMMUTABLE_NFT_DATA).input_type_script(0)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_transfer_with_modified_metadata_failure",
+        "nft-validator-sim",
+        ERROR_IMMUTABLE_NFT_DATA,
+    );
+}
+
+#[test]
+fn test_nft_update_metadata_in_governance_mode() {
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let nft_id = random_32bytes();
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            build_nft_data(&nft_id, b"original metadata"),
+        ))
+        .input(Cell::new(&governance_script, 10000))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            999,
+            build_nft_data(&nft_id, b"modified metadata"),
+        ))
+        .output(Cell::new(&governance_script, 10000))
+        .run_input_type(0);
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_update_metadata_in_governance_mode", cycles);
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_update_metadata_in_governance_mode",
+        "nft-validator-sim",
+        0,
+    );
+}
+
+#[test]
+fn test_nft_generation_with_supply_counter() {
+    let (mut scenario, governance_script, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&governance_script, 10000));
+    let input = scenario.inputs()[0].clone();
+    // The supply counter is created at output 1 alongside the first NFT
+    let counter_id = derive_id(&input, 1);
+    let nft_type_script = scenario.script(
+        "nft-validator",
+        build_supply_args(&governance_script.calc_script_hash(), 10, 0, &counter_id),
+    );
+    scenario
+        .output(Cell::new(&governance_script, 9000))
+        .output(nft_cell(
+            &governance_script,
+            &nft_type_script,
+            500,
+            supply_counter_data(1),
+        ))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&derive_id(&input, 2), &[]),
+        ))
+        .run_output_type(1);
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_generation_with_supply_counter", cycles);
+
+    // dump raw test tx files
+    scenario.dump("nft_generation_with_supply_counter", "nft-validator-sim", 0);
+}
+
+#[test]
+fn test_nft_generation_exceeding_supply_cap() {
+    let (mut scenario, governance_script, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let nft_type_script = scenario.script(
+        "nft-validator",
+        build_supply_args(
+            &governance_script.calc_script_hash(),
+            2,
+            0,
+            &random_32bytes(),
+        ),
+    );
+    scenario
+        .input(Cell::new(&governance_script, 10000))
+        .input(nft_cell(
+            &governance_script,
+            &nft_type_script,
+            500,
+            supply_counter_data(2),
+        ));
+    let nft_id = derive_id(&scenario.inputs()[0], 2);
+    scenario
+        .output(Cell::new(&governance_script, 9500))
+        .output(nft_cell(
+            &governance_script,
+            &nft_type_script,
+            500,
+            supply_counter_data(3),
+        ))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&nft_id, &[]),
+        ))
+        .run_input_type(1);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_SUPPLY_CAP_EXCEEDED).input_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_exceeding_supply_cap_failure",
+        "nft-validator-sim",
+        ERROR_SUPPLY_CAP_EXCEEDED,
+    );
+}
+
+#[test]
+fn test_nft_generation_exceeding_mint_limit() {
+    let (mut scenario, governance_script, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    scenario.input(Cell::new(&governance_script, 10000));
+    let input = scenario.inputs()[0].clone();
+    // Only 1 NFT can be generated per transaction
+    let counter_id = derive_id(&input, 1);
+    let nft_type_script = scenario.script(
+        "nft-validator",
+        build_supply_args(&governance_script.calc_script_hash(), 10, 1, &counter_id),
+    );
+    scenario
+        .output(Cell::new(&governance_script, 8500))
+        .output(nft_cell(
+            &governance_script,
+            &nft_type_script,
+            500,
+            supply_counter_data(2),
+        ))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&derive_id(&input, 2), &[]),
+        ))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&derive_id(&input, 3), &[]),
+        ))
+        .run_output_type(1);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_MINT_LIMIT_EXCEEDED).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_exceeding_mint_limit_failure",
+        "nft-validator-sim",
+        ERROR_MINT_LIMIT_EXCEEDED,
+    );
+}
+
+#[test]
+fn test_nft_generation_without_supply_counter() {
+    let (mut scenario, governance_script, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let nft_type_script = scenario.script(
+        "nft-validator",
+        build_supply_args(
+            &governance_script.calc_script_hash(),
+            10,
+            0,
+            &random_32bytes(),
+        ),
+    );
+    scenario.input(Cell::new(&governance_script, 10000));
+    let nft_id = derive_id(&scenario.inputs()[0], 1);
+    scenario
+        .output(Cell::new(&governance_script, 9500))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&nft_id, &[]),
+        ))
+        .run_output_type(1);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_SUPPLY_COUNTER).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_without_supply_counter_failure",
+        "nft-validator-sim",
+        ERROR_INVALID_SUPPLY_COUNTER,
+    );
+}
+
+/// Generates an NFT governed by 3 governance locks with `threshold`, the
+/// first `present` of them unlock inputs of the transaction.
+fn multi_governance_scenario(threshold: u8, present: usize) -> TxScenario {
+    let (mut scenario, _, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let governance_scripts: Vec<Script> = (0..3).map(|_| scenario.lock_script()).collect();
+    let governance_script_hashes: Vec<Byte32> = governance_scripts
+        .iter()
+        .map(|script| script.calc_script_hash())
+        .collect();
+    let nft_type_script = scenario.script(
+        "nft-validator",
+        build_multi_governance_args(&governance_script_hashes, threshold),
+    );
+    for governance_script in &governance_scripts[..present] {
+        scenario.input(Cell::new(governance_script, 5000));
+    }
+    let nft_id = derive_id(&scenario.inputs()[0], 1);
+    scenario
+        .output(Cell::new(&governance_scripts[0], 4500))
+        .output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            499,
+            build_nft_data(&nft_id, &[]),
+        ))
+        .run_output_type(1);
+    scenario
+}
+
+#[test]
+fn test_nft_generation_with_governance_threshold_met() {
+    // 2 of the 3 governance locks are required
+    let scenario = multi_governance_scenario(2, 2);
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_generation_with_governance_threshold_met", cycles);
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_with_governance_threshold_met",
+        "nft-validator-sim",
+        0,
+    );
+}
+
+#[test]
+fn test_nft_generation_with_governance_threshold_unmet() {
+    // 2 of the 3 governance locks are required
+    let scenario = multi_governance_scenario(2, 1);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_REQUIRE_GOVERNANCE_MODE).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_with_governance_threshold_unmet_failure",
+        "nft-validator-sim",
+        ERROR_REQUIRE_GOVERNANCE_MODE,
+    );
+}
+
+#[test]
+fn test_nft_generation_with_malformed_governance_args() {
+    // Threshold is larger than the number of governance locks
+    let scenario = multi_governance_scenario(4, 3);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_ARGUMENT).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_with_malformed_governance_args_failure",
+        "nft-validator-sim",
+        ERROR_INVALID_ARGUMENT,
+    );
+}
+
+#[test]
+fn test_nft_transfer_frozen() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_data = build_nft_data_with_flags(&random_32bytes(), &[], NFT_FLAG_FROZEN);
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            nft_data.clone(),
+        ))
+        .output(nft_cell(&lock_script2, &nft_type_script, 999, nft_data))
+        .run_input_type(0);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_NFT_FROZEN).input_type_script(0)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_transfer_frozen_failure",
+        "nft-validator-sim",
+        ERROR_NFT_FROZEN,
+    );
+}
+
+#[test]
+fn test_nft_transfer_soulbound() {
+    let (mut scenario, _, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_data = build_nft_data_with_flags(&random_32bytes(), &[], NFT_FLAG_SOULBOUND);
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            nft_data.clone(),
+        ))
+        .output(nft_cell(&lock_script2, &nft_type_script, 999, nft_data))
+        .run_input_type(0);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_NFT_FROZEN).input_type_script(0)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_transfer_soulbound_failure",
+        "nft-validator-sim",
+        ERROR_NFT_FROZEN,
+    );
+}
+
+#[test]
+fn test_nft_transfer_frozen_in_governance_mode() {
+    let (mut scenario, governance_script, nft_type_script) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let nft_data = build_nft_data_with_flags(&random_32bytes(), &[], NFT_FLAG_FROZEN);
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            nft_data.clone(),
+        ))
+        .input(Cell::new(&governance_script, 10000))
+        .output(nft_cell(&lock_script2, &nft_type_script, 999, nft_data))
+        .run_input_type(0);
 
     // run
-    assert!(context.verify_tx(&tx, MAX_CYCLES).is_err());
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_transfer_frozen_in_governance_mode", cycles);
 
     // dump raw test tx files
-    let setup = RunningSetup {
-        is_lock_script: false,
-        is_output: true,
-        script_index: 1,
-        native_binaries: HashMap::default(),
+    scenario.dump(
+        "nft_transfer_frozen_in_governance_mode",
+        "nft-validator-sim",
+        0,
+    );
+}
+
+/// Transfers an NFT with a royalty of 500, paying `paid` to the royalty lock,
+/// or to the buyer lock unless `pay_royalty_lock`.
+fn royalty_scenario(paid: u64, pay_royalty_lock: bool) -> TxScenario {
+    let (mut scenario, governance_script, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let lock_script2 = scenario.lock_script();
+    let royalty_script = scenario.lock_script();
+    let nft_type_script = scenario.script(
+        "nft-validator",
+        build_royalty_args(
+            &governance_script.calc_script_hash(),
+            &royalty_script.calc_script_hash(),
+            500,
+        ),
+    );
+    let payee = if pay_royalty_lock {
+        &royalty_script
+    } else {
+        &lock_script2
     };
-    write_native_setup("nft_invalid_nft_data_failure", "nft-validator-sim", &tx, &context, &setup);
+    let nft_data = build_nft_data(&random_32bytes(), &[]);
+    scenario
+        .input(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            1000,
+            nft_data.clone(),
+        ))
+        .input(Cell::new(&lock_script2, 1000))
+        .output(nft_cell(&lock_script2, &nft_type_script, 1000, nft_data))
+        .output(Cell::new(payee, paid))
+        .run_input_type(0);
+    scenario
 }
 
 #[test]
-fn test_nft_invalid_nft_hash() {
-    // deploy contract
-    let mut context = Context::default();
-    let nft_bin: Bytes = Loader::default().load_binary("nft-validator");
-    let nft_out_point = context.deploy_cell(nft_bin);
-    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
-
-    // prepare scripts
-    let lock_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let lock_script_dep = CellDep::new_builder()
-        .out_point(always_success_out_point.clone())
-        .build();
-    let governance_script = context
-        .build_script(&always_success_out_point, random_32bytes())
-        .expect("lock script");
-    let governance_script_hash = governance_script.calc_script_hash();
-    let nft_type_script = context
-        .build_script(&nft_out_point, governance_script_hash.raw_data())
-        .expect("script");
-    let nft_script_dep = CellDep::new_builder()
-        .out_point(nft_out_point.clone())
-        .build();
+fn test_nft_transfer_with_royalty() {
+    let scenario = royalty_scenario(500, true);
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_transfer_with_royalty", cycles);
+
+    // dump raw test tx files
+    scenario.dump("nft_transfer_with_royalty", "nft-validator-sim", 0);
+}
+
+#[test]
+fn test_nft_transfer_with_insufficient_royalty() {
+    let scenario = royalty_scenario(499, true);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ROYALTY_NOT_PAID).input_type_script(0)
+    );
 
-    // prepare cells
-    let input_out_point = context.create_cell(
-        CellOutput::new_builder()
-            .capacity(10000u64.pack())
-            .lock(governance_script.clone())
-            .build(),
-        Bytes::new(),
+    // dump raw test tx files
+    scenario.dump(
+        "nft_transfer_with_insufficient_royalty_failure",
+        "nft-validator-sim",
+        ERROR_ROYALTY_NOT_PAID,
     );
-    let input = CellInput::new_builder()
-        .previous_output(input_out_point)
-        .build();
+}
 
-    let mut blake2b = Blake2bBuilder::new(32)
-        .personal(b"ckb-default-hash")
-        .build();
-    blake2b.update(input.as_slice());
-    blake2b.update(&1u64.to_le_bytes());
-    let mut hash = vec![0u8; 32];
-    blake2b.finalize(&mut hash[..]);
-    hash[0] += 1;
-    let nft_id = Bytes::from(hash);
-
-    let outputs = vec![
-        CellOutput::new_builder()
-            .capacity(9500u64.pack())
-            .lock(governance_script.clone())
-            .build(),
-        CellOutput::new_builder()
-            .capacity(499u64.pack())
-            .lock(lock_script.clone())
-            .type_(
-                ScriptOpt::new_builder()
-                    .set(Some(nft_type_script.clone()))
-                    .build(),
-            )
-            .build(),
-    ];
-
-    let outputs_data = vec![Bytes::new(), nft_id];
-
-    // build transaction
-    let tx = TransactionBuilder::default()
-        .input(input)
-        .outputs(outputs)
-        .outputs_data(outputs_data.pack())
-        .cell_dep(lock_script_dep)
-        .cell_dep(nft_script_dep)
-        .build();
-    let tx = context.complete_tx(tx);
+#[test]
+fn test_nft_transfer_without_royalty() {
+    let scenario = royalty_scenario(500, false);
 
     // run
-    assert!(context.verify_tx(&tx, MAX_CYCLES).is_err());
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ROYALTY_NOT_PAID).input_type_script(0)
+    );
 
     // dump raw test tx files
-    let setup = RunningSetup {
-        is_lock_script: false,
-        is_output: true,
-        script_index: 1,
-        native_binaries: HashMap::default(),
+    scenario.dump(
+        "nft_transfer_without_royalty_failure",
+        "nft-validator-sim",
+        ERROR_ROYALTY_NOT_PAID,
+    );
+}
+
+/// Generates NFTs of a class, updating the class cell from `issued` to
+/// `new_issued` with a max supply of 10. The class cell is governed by
+/// nft-validator in class cell mode. `serial_ids` selects the class serial
+/// ID scheme, the NFT IDs are returned by `nft_ids`.
+fn class_scenario<F>(serial_ids: bool, issued: u64, new_issued: u64, nft_ids: F) -> TxScenario
+where
+    F: Fn(&CellInput, &Byte32) -> Vec<Bytes>,
+{
+    let (mut scenario, governance_script, _) = nft_scenario();
+    let lock_script = scenario.lock_script();
+    let class_type_script = scenario.script(
+        "nft-validator",
+        build_class_cell_args(&governance_script.calc_script_hash(), &random_32bytes()),
+    );
+    let class_type_hash = class_type_script.calc_script_hash();
+    let nft_args = if serial_ids {
+        build_class_serial_args(&governance_script.calc_script_hash(), &class_type_hash)
+    } else {
+        build_class_args(&governance_script.calc_script_hash(), &class_type_hash)
     };
-    write_native_setup("nft_invalid_nft_hash_failure", "nft-validator-sim", &tx, &context, &setup);
+    let nft_type_script = scenario.script("nft-validator", nft_args);
+    scenario
+        .input(Cell::new(&governance_script, 20000))
+        .input(
+            Cell::new(&governance_script, 1000)
+                .type_(&class_type_script)
+                .data(build_class_data(b"test", issued, 10)),
+        )
+        .output(
+            Cell::new(&governance_script, 1000)
+                .type_(&class_type_script)
+                .data(build_class_data(b"test", new_issued, 10)),
+        );
+    let first_input = scenario.inputs()[0].clone();
+    for nft_id in nft_ids(&first_input, &class_type_hash) {
+        scenario.output(nft_cell(
+            &lock_script,
+            &nft_type_script,
+            9999,
+            build_nft_data(&nft_id, &[]),
+        ));
+    }
+    scenario.run_output_type(1);
+    scenario
+}
+
+#[test]
+fn test_nft_generation_with_class() {
+    let scenario = class_scenario(false, 3, 4, |input, _| vec![derive_id(input, 1)]);
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_generation_with_class", cycles);
+
+    // dump raw test tx files
+    scenario.dump("nft_generation_with_class", "nft-validator-sim", 0);
+}
+
+#[test]
+fn test_nft_generation_without_class_update() {
+    let scenario = class_scenario(false, 3, 3, |input, _| vec![derive_id(input, 1)]);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_CLASS_CELL).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_without_class_update_failure",
+        "nft-validator-sim",
+        ERROR_INVALID_CLASS_CELL,
+    );
+}
+
+#[test]
+fn test_nft_generation_exceeding_class_supply() {
+    let scenario = class_scenario(false, 10, 11, |input, _| vec![derive_id(input, 1)]);
+
+    // run, both the class cell and the NFT scripts reject the transaction,
+    // either one may be reported
+    let err = scenario.verify(MAX_CYCLES).unwrap_err().to_string();
+    let expected: Vec<String> = vec![
+        ScriptError::ValidationFailure(ERROR_SUPPLY_CAP_EXCEEDED).input_type_script(1),
+        ScriptError::ValidationFailure(ERROR_SUPPLY_CAP_EXCEEDED).output_type_script(1),
+    ]
+    .into_iter()
+    .map(|failure| Error::from(failure).to_string())
+    .collect();
+    assert!(expected.contains(&err), "unexpected error: {}", err);
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_exceeding_class_supply_failure",
+        "nft-validator-sim",
+        ERROR_SUPPLY_CAP_EXCEEDED,
+    );
+}
+
+#[test]
+fn test_nft_generation_with_class_serial_ids() {
+    let scenario = class_scenario(true, 3, 5, |_, class_type_hash| {
+        vec![
+            derive_serial_id(class_type_hash, 3),
+            derive_serial_id(class_type_hash, 4),
+        ]
+    });
+
+    // run
+    let cycles = scenario.verify(MAX_CYCLES).expect("pass verification");
+    check_cycles("nft_generation_with_class_serial_ids", cycles);
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_with_class_serial_ids",
+        "nft-validator-sim",
+        0,
+    );
+}
+
+#[test]
+fn test_nft_generation_with_input_based_id_in_class_serial_scheme() {
+    let scenario = class_scenario(true, 3, 4, |input, _| vec![derive_id(input, 1)]);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_NFT).output_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "nft_generation_with_input_based_id_in_class_serial_scheme_failure",
+        "nft-validator-sim",
+        ERROR_INVALID_NFT,
+    );
+}
+
+#[test]
+fn test_class_cell_with_lowered_issued() {
+    // Lowering the issued counter without generating NFTs would allow
+    // serial IDs to be reused
+    let mut scenario = class_scenario(true, 3, 2, |_, _| vec![]);
+    scenario.run_input_type(1);
+
+    // run
+    let err = scenario.verify(MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_CLASS_CELL).input_type_script(1)
+    );
+
+    // dump raw test tx files
+    scenario.dump(
+        "class_cell_with_lowered_issued_failure",
+        "nft-validator-sim",
+        ERROR_INVALID_CLASS_CELL,
+    );
 }
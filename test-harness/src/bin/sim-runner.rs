@@ -0,0 +1,73 @@
+//! Runs the test cases dumped by `cargo test` against the native simulator
+//! binaries and checks their exit codes against each case manifest.
+//!
+//! Usage: sim-runner <dumped_tests dir> [--jobs N] [--report PREFIX] [--filter PATTERN]
+//!
+//! Reports are written to `PREFIX.json` and `PREFIX.xml` (JUnit), by default
+//! `sim_report` next to the dumped tests folder.
+
+use ckb_contract_test_harness::sim::{discover, run_all, write_json_report, write_junit_report};
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: sim-runner <dumped_tests dir> [--jobs N] [--report PREFIX] [--filter PATTERN]"
+    );
+    process::exit(2);
+}
+
+fn main() {
+    let mut dir = None;
+    let mut jobs = num_cpus::get();
+    let mut report = None;
+    let mut filter = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--jobs" => {
+                jobs = match args.next().map(|val| val.parse()) {
+                    Some(Ok(jobs)) => jobs,
+                    _ => usage(),
+                }
+            }
+            "--report" => report = Some(PathBuf::from(args.next().unwrap_or_else(|| usage()))),
+            "--filter" => filter = Some(args.next().unwrap_or_else(|| usage())),
+            _ if dir.is_none() && !arg.starts_with("--") => dir = Some(PathBuf::from(arg)),
+            _ => usage(),
+        }
+    }
+    let dir = dir.unwrap_or_else(|| usage());
+    let report = report.unwrap_or_else(|| dir.with_file_name("sim_report"));
+
+    let mut cases = discover(&dir).expect("discover dumped tests");
+    if let Some(filter) = filter {
+        cases.retain(|case| case.name.contains(&filter));
+    }
+    let results = run_all(&cases, jobs);
+    for result in &results {
+        match &result.failure {
+            Some(failure) => {
+                println!("FAIL {} ({}): {}", result.case, result.binary, failure);
+                if !result.stderr.is_empty() {
+                    println!("{}", result.stderr);
+                }
+            }
+            None => println!("PASS {} ({})", result.case, result.binary),
+        }
+    }
+    write_json_report(&results, &report.with_extension("json")).expect("write json report");
+    write_junit_report(&results, &report.with_extension("xml")).expect("write junit report");
+
+    let failures = results.iter().filter(|r| r.failure.is_some()).count();
+    println!(
+        "{} cases, {} runs, {} failed",
+        cases.len(),
+        results.len(),
+        failures
+    );
+    if failures > 0 || results.is_empty() {
+        process::exit(1);
+    }
+}
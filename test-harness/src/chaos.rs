@@ -0,0 +1,164 @@
+use crate::native::dep_group_members;
+use ckb_testtool::context::Context;
+use ckb_tool::ckb_error::Error;
+use ckb_tool::ckb_script::{
+    cost_model::instruction_cycles, ScriptGroupType, TransactionScriptsVerifier,
+};
+use ckb_tool::ckb_types::{
+    core::{
+        cell::{CellMeta, CellMetaBuilder, ResolvedTransaction},
+        Cycle, DepType, TransactionView,
+    },
+    packed::{Byte32, OutPoint},
+    prelude::*,
+};
+use ckb_vm::{
+    machine::asm::{AsmCoreMachine, AsmMachine},
+    DefaultMachineBuilder,
+};
+use rand::{thread_rng, Rng};
+use std::env;
+
+/// Number of chaos mode runs per transaction, README Rule 2 asks for at least 20.
+const DEFAULT_CHAOS_RUNS: usize = 20;
+/// Overrides the number of chaos mode runs.
+const CHAOS_RUNS_ENV_VAR: &str = "CHAOS_RUNS";
+/// Replays a single chaos mode run with the given seed, as printed by a failed test.
+const CHAOS_SEED_ENV_VAR: &str = "CHAOS_SEED";
+
+/// Verifies `tx` with the normal verifier, then runs every script group again
+/// in CKB-VM chaos mode, which randomizes memory initialization from a seed.
+/// Panics with the seed when a chaos run exits differently from the normal run,
+/// otherwise returns the result of the normal verifier.
+pub fn verify_tx_all_modes(
+    context: &Context,
+    tx: &TransactionView,
+    max_cycles: Cycle,
+) -> Result<Cycle, Error> {
+    let result = context.verify_tx(tx, max_cycles);
+
+    let rtx = resolve_tx(context, tx);
+    let verifier = TransactionScriptsVerifier::new(&rtx, context);
+    let groups = script_groups(&rtx);
+    let seeds = chaos_seeds();
+    for (group_type, script_hash) in &groups {
+        let expected = run_script_group(&verifier, *group_type, script_hash, max_cycles, None);
+        for seed in &seeds {
+            let actual =
+                run_script_group(&verifier, *group_type, script_hash, max_cycles, Some(*seed));
+            if actual != expected {
+                panic!(
+                    "chaos mode run of {:?} script {} diverged, normal: {:?}, chaos: {:?}, \
+                     replay with {}={}",
+                    group_type, script_hash, expected, actual, CHAOS_SEED_ENV_VAR, seed
+                );
+            }
+        }
+    }
+    result
+}
+
+fn chaos_seeds() -> Vec<u32> {
+    if let Ok(seed) = env::var(CHAOS_SEED_ENV_VAR) {
+        return vec![seed.parse().expect("chaos seed")];
+    }
+    let runs = match env::var(CHAOS_RUNS_ENV_VAR) {
+        Ok(val) => val.parse().expect("chaos runs"),
+        Err(_) => DEFAULT_CHAOS_RUNS,
+    };
+    let mut rng = thread_rng();
+    (0..runs).map(|_| rng.gen()).collect()
+}
+
+fn resolve_cell(context: &Context, out_point: &OutPoint) -> CellMeta {
+    let (output, data) = context.get_cell(out_point).expect("get cell");
+    let mut builder = CellMetaBuilder::from_cell_output(output, data).out_point(out_point.clone());
+    if let Some(info) = context.transaction_infos.get(out_point) {
+        builder = builder.transaction_info(info.clone());
+    }
+    builder.build()
+}
+
+pub(crate) fn resolve_tx(context: &Context, tx: &TransactionView) -> ResolvedTransaction {
+    let resolved_inputs = tx
+        .inputs()
+        .into_iter()
+        .map(|input| resolve_cell(context, &input.previous_output()))
+        .collect();
+    let mut resolved_cell_deps = Vec::new();
+    let mut resolved_dep_groups = Vec::new();
+    for cell_dep in tx.cell_deps().into_iter() {
+        if cell_dep.dep_type() == DepType::DepGroup.into() {
+            let (_, data) = context.get_cell(&cell_dep.out_point()).expect("get cell");
+            for out_point in dep_group_members(&data) {
+                resolved_cell_deps.push(resolve_cell(context, &out_point));
+            }
+            resolved_dep_groups.push(resolve_cell(context, &cell_dep.out_point()));
+        } else {
+            resolved_cell_deps.push(resolve_cell(context, &cell_dep.out_point()));
+        }
+    }
+    ResolvedTransaction {
+        transaction: tx.clone(),
+        resolved_cell_deps,
+        resolved_inputs,
+        resolved_dep_groups,
+    }
+}
+
+fn script_groups(rtx: &ResolvedTransaction) -> Vec<(ScriptGroupType, Byte32)> {
+    let mut groups: Vec<(ScriptGroupType, Byte32)> = Vec::new();
+    let mut push = |group_type, script_hash| {
+        if !groups.contains(&(group_type, script_hash.clone())) {
+            groups.push((group_type, script_hash));
+        }
+    };
+    for cell in &rtx.resolved_inputs {
+        push(
+            ScriptGroupType::Lock,
+            cell.cell_output.lock().calc_script_hash(),
+        );
+        if let Some(type_script) = cell.cell_output.type_().to_opt() {
+            push(ScriptGroupType::Type, type_script.calc_script_hash());
+        }
+    }
+    for output in rtx.transaction.outputs() {
+        if let Some(type_script) = output.type_().to_opt() {
+            push(ScriptGroupType::Type, type_script.calc_script_hash());
+        }
+    }
+    groups
+}
+
+/// Runs a single script group and returns its exit code, enabling chaos mode
+/// when a seed is given.
+pub(crate) fn run_script_group(
+    verifier: &TransactionScriptsVerifier<Context>,
+    group_type: ScriptGroupType,
+    script_hash: &Byte32,
+    max_cycles: Cycle,
+    chaos_seed: Option<u32>,
+) -> Result<i8, String> {
+    let group = verifier
+        .find_script_group(group_type, script_hash)
+        .expect("script group");
+    let program = verifier
+        .extract_script(&group.script)
+        .map_err(|err| err.to_string())?;
+    let mut core_machine = AsmCoreMachine::new_with_max_cycles(max_cycles);
+    if let Some(seed) = chaos_seed {
+        core_machine.chaos_mode = 1;
+        core_machine.chaos_seed = seed;
+    }
+    let machine_builder = DefaultMachineBuilder::<Box<AsmCoreMachine>>::new(core_machine)
+        .instruction_cycle_func(Box::new(instruction_cycles));
+    let machine_builder = verifier
+        .generate_syscalls(group)
+        .into_iter()
+        .fold(machine_builder, |builder, syscall| builder.syscall(syscall));
+    let mut machine = AsmMachine::new(machine_builder.build(), None);
+    machine
+        .load_program(&program, &[])
+        .map_err(|err| format!("{:?}", err))?;
+    machine.run().map_err(|err| format!("{:?}", err))
+}
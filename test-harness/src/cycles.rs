@@ -0,0 +1,81 @@
+use ckb_tool::ckb_types::core::Cycle;
+use serde_json::to_string_pretty;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Cycles baseline kept next to the Cargo.toml of the test crate.
+const BASELINE_FILE: &str = "cycles.json";
+/// Set to `1` to record the cycles of each test into the baseline.
+const UPDATE_ENV_VAR: &str = "UPDATE_CYCLES";
+/// Allowed increase over the baseline in percent.
+const TOLERANCE_ENV_VAR: &str = "CYCLES_TOLERANCE";
+const DEFAULT_TOLERANCE: f64 = 5.0;
+
+lazy_static! {
+    // Tests run in parallel, the baseline file is updated under this lock.
+    static ref BASELINE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn baseline_path() -> PathBuf {
+    let dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR");
+    Path::new(&dir).join(BASELINE_FILE)
+}
+
+fn load_baseline(path: &Path) -> BTreeMap<String, Cycle> {
+    match fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data).expect("parse cycles baseline"),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn tolerance() -> f64 {
+    match env::var(TOLERANCE_ENV_VAR) {
+        Ok(val) => val.parse().expect("cycles tolerance"),
+        Err(_) => DEFAULT_TOLERANCE,
+    }
+}
+
+/// Compares the cycles consumed by `test_name` with the checked-in baseline,
+/// panicking when they grow beyond the tolerance or the test has no baseline
+/// yet. With `UPDATE_CYCLES=1` the baseline is updated instead.
+pub fn check_cycles(test_name: &str, cycles: Cycle) {
+    println!("consume cycles: {}", cycles);
+    let path = baseline_path();
+    let expected = {
+        let _guard = BASELINE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let mut baseline = load_baseline(&path);
+        if env::var(UPDATE_ENV_VAR)
+            .map(|val| val == "1")
+            .unwrap_or(false)
+        {
+            baseline.insert(test_name.to_string(), cycles);
+            let json = to_string_pretty(&baseline).expect("serialize to json");
+            fs::write(&path, json + "\n").expect("write cycles baseline");
+            return;
+        }
+        baseline.get(test_name).cloned()
+    };
+    match expected {
+        Some(expected) => {
+            let limit = expected as f64 * (1.0 + tolerance() / 100.0);
+            if cycles as f64 > limit {
+                panic!(
+                    "{} consumes {} cycles, {} more than the baseline {}, run with {}=1 \
+                     to accept the increase",
+                    test_name,
+                    cycles,
+                    cycles - expected,
+                    expected,
+                    UPDATE_ENV_VAR
+                );
+            }
+        }
+        None => panic!(
+            "no cycles baseline for {}, run with {}=1 to record it",
+            test_name, UPDATE_ENV_VAR
+        ),
+    }
+}
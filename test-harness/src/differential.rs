@@ -0,0 +1,74 @@
+use crate::chaos::{resolve_tx, run_script_group};
+use crate::sim::{run_one, SimRun};
+use ckb_testtool::context::Context;
+use ckb_tool::ckb_script::{ScriptGroupType, TransactionScriptsVerifier};
+use ckb_tool::ckb_types::{core::TransactionView, packed::Script, prelude::*};
+use ckb_x64_simulator::RunningSetup;
+use std::env;
+
+/// Set to `1` to run every dumped test in the native simulator right away and
+/// compare its exit code with CKB-VM.
+const DIFFERENTIAL_ENV_VAR: &str = "CKB_DIFFERENTIAL_TEST";
+
+pub fn differential_enabled() -> bool {
+    env::var(DIFFERENTIAL_ENV_VAR)
+        .map(|val| val == "1")
+        .unwrap_or(false)
+}
+
+/// Runs the script selected by `setup` in CKB-VM and the native simulator run
+/// `run` of the same transaction, panicking when their exit codes differ.
+pub fn compare_vm_and_native(
+    test_name: &str,
+    tx: &TransactionView,
+    context: &Context,
+    setup: &RunningSetup,
+    run: &SimRun,
+) {
+    let (group_type, script) = selected_script(tx, context, setup);
+    let rtx = resolve_tx(context, tx);
+    let verifier = TransactionScriptsVerifier::new(&rtx, context);
+    let vm_result = run_script_group(
+        &verifier,
+        group_type,
+        &script.calc_script_hash(),
+        u64::max_value(),
+        None,
+    );
+    let native = run_one(test_name, run);
+    // Exit codes are a single byte, scripts return them as i8.
+    let native_code = native.exit_code.map(|code| code as u8 as i8);
+    match vm_result {
+        Ok(code) if Some(code) == native_code => {}
+        vm_result => panic!(
+            "{}: CKB-VM returns {:?} but native simulator {} exits with {:?}\n{}",
+            test_name, vm_result, native.binary, native_code, native.stderr
+        ),
+    }
+}
+
+pub(crate) fn selected_script(
+    tx: &TransactionView,
+    context: &Context,
+    setup: &RunningSetup,
+) -> (ScriptGroupType, Script) {
+    let input_cell = |index: usize| {
+        let input = tx.inputs().get(index).expect("input");
+        let (output, _) = context
+            .get_cell(&input.previous_output())
+            .expect("get cell");
+        output
+    };
+    if setup.is_lock_script {
+        let lock = input_cell(setup.script_index).lock();
+        (ScriptGroupType::Lock, lock)
+    } else {
+        let output = if setup.is_output {
+            tx.outputs().get(setup.script_index).expect("output")
+        } else {
+            input_cell(setup.script_index)
+        };
+        let type_script = output.type_().to_opt().expect("type script");
+        (ScriptGroupType::Type, type_script)
+    }
+}
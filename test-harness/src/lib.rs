@@ -0,0 +1,157 @@
+#[macro_use]
+extern crate lazy_static;
+
+use ckb_tool::ckb_types::bytes::Bytes;
+use rand::{thread_rng, Rng};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub mod chaos;
+pub mod cycles;
+pub mod differential;
+pub mod mutation;
+pub mod native;
+pub mod scenario;
+pub mod sim;
+
+pub use chaos::verify_tx_all_modes;
+pub use cycles::check_cycles;
+pub use mutation::{check_mutants, Mutation};
+pub use native::{build_mock_transaction, create_test_folder, rewrite_setup, write_native_setup};
+pub use scenario::{Cell, TxScenario};
+
+lazy_static! {
+    static ref LOADER: Loader = Loader::default();
+    pub(crate) static ref TX_FOLDER: PathBuf = {
+        let path = LOADER.path("dumped_tests");
+        if Path::new(&path).exists() {
+            fs::remove_dir_all(&path).expect("remove old dir");
+        }
+        fs::create_dir_all(&path).expect("create test dir");
+        path
+    };
+}
+
+const TEST_ENV_VAR: &str = "CAPSULE_TEST_ENV";
+/// Overrides the folder binaries are loaded from, e.g. a custom cargo or
+/// capsule out-dir. Relative paths are resolved against the test crate.
+const BUILD_DIR_ENV_VAR: &str = "CAPSULE_BUILD_DIR";
+
+/// Build profile, picking the `build/<profile>` folder binaries are loaded from.
+pub enum TestEnv {
+    Debug,
+    Release,
+    /// Any other profile, e.g. `coverage` or `asan`.
+    Custom(String),
+}
+
+impl FromStr for TestEnv {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "debug" => Ok(TestEnv::Debug),
+            "release" => Ok(TestEnv::Release),
+            "" => Err("no match"),
+            _ if s.contains(|c| c == '/' || c == '\\') => Err("invalid profile"),
+            // Custom profiles name a build folder, keep them as given
+            _ => Ok(TestEnv::Custom(s.to_string())),
+        }
+    }
+}
+
+impl TestEnv {
+    fn profile(&self) -> &str {
+        match self {
+            TestEnv::Debug => "debug",
+            TestEnv::Release => "release",
+            TestEnv::Custom(profile) => profile,
+        }
+    }
+}
+
+/// Loads contract binaries from the build folders, searched in order.
+pub struct Loader(Vec<PathBuf>);
+
+impl Default for Loader {
+    fn default() -> Self {
+        if let Ok(dir) = env::var(BUILD_DIR_ENV_VAR) {
+            return Self::with_dir(test_crate_dir().join(dir));
+        }
+        let test_env = match env::var(TEST_ENV_VAR) {
+            Ok(val) => val.parse().expect("test env"),
+            Err(_) => TestEnv::Debug,
+        };
+        Self::with_test_env(test_env)
+    }
+}
+
+/// Folder of the test crate, `CARGO_MANIFEST_DIR` when run by cargo.
+fn test_crate_dir() -> PathBuf {
+    match env::var("CARGO_MANIFEST_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => env::current_dir().expect("current dir"),
+    }
+}
+
+impl Loader {
+    /// Loads binaries from `../build/<profile>` of the test crate, falling back
+    /// to the same folder relative to the current directory.
+    pub fn with_test_env(env: TestEnv) -> Self {
+        let mut dirs = vec![test_crate_dir()
+            .join("..")
+            .join("build")
+            .join(env.profile())];
+        let cwd_dir = env::current_dir()
+            .expect("current dir")
+            .join("..")
+            .join("build")
+            .join(env.profile());
+        if !dirs.contains(&cwd_dir) {
+            dirs.push(cwd_dir);
+        }
+        Loader(dirs)
+    }
+
+    /// Loads binaries from `dir` only.
+    pub fn with_dir<P: Into<PathBuf>>(dir: P) -> Self {
+        Loader(vec![dir.into()])
+    }
+
+    /// Returns the first existing `name` in the build folders, or its path in
+    /// the first folder when it exists in none.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.0
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| self.0[0].join(name))
+    }
+
+    pub fn load_binary(&self, name: &str) -> Bytes {
+        for dir in &self.0 {
+            if let Ok(data) = fs::read(dir.join(name)) {
+                return data.into();
+            }
+        }
+        let searched: Vec<String> = self
+            .0
+            .iter()
+            .map(|dir| dir.join(name).display().to_string())
+            .collect();
+        panic!(
+            "binary {} not found, searched: {}",
+            name,
+            searched.join(", ")
+        );
+    }
+}
+
+pub fn random_32bytes() -> Bytes {
+    let mut rng = thread_rng();
+    let mut buf = vec![0u8; 32];
+    rng.fill(&mut buf[..]);
+    Bytes::from(buf)
+}
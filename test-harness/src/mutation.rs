@@ -0,0 +1,235 @@
+use crate::differential::selected_script;
+use crate::{verify_tx_all_modes, write_native_setup};
+use ckb_testtool::context::Context;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Cycle, TransactionView},
+    packed::{CellOutput, Script, ScriptOpt},
+    prelude::*,
+};
+use ckb_x64_simulator::RunningSetup;
+use std::fmt;
+
+/// A systematic change to a passing transaction, expected to make it fail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mutation {
+    /// Removes the witness at the index.
+    DropWitness(usize),
+    /// Removes the input at the index, e.g. the governance input.
+    DropInput(usize),
+    /// Inverts the last byte of the data of the output at the index, which
+    /// changes its content rather than a molecule header.
+    FlipOutputData(usize),
+    /// Swaps two adjacent outputs along with their data.
+    SwapOutputs(usize, usize),
+    /// Drops the last byte of the args of the script selected by the
+    /// `RunningSetup`, in every input and output using it.
+    TruncateArgs,
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mutation::DropWitness(index) => write!(f, "drop_witness_{}", index),
+            Mutation::DropInput(index) => write!(f, "drop_input_{}", index),
+            Mutation::FlipOutputData(index) => write!(f, "flip_output_data_{}", index),
+            Mutation::SwapOutputs(a, b) => write!(f, "swap_outputs_{}_{}", a, b),
+            Mutation::TruncateArgs => write!(f, "truncate_args"),
+        }
+    }
+}
+
+/// Lists the mutations applicable to `tx`, `script` is the selected script.
+fn mutations(tx: &TransactionView, script: &Script) -> Vec<Mutation> {
+    let mut mutations: Vec<Mutation> = (0..tx.witnesses().len())
+        .map(Mutation::DropWitness)
+        .collect();
+    mutations.extend((0..tx.inputs().len()).map(Mutation::DropInput));
+    let outputs_data = tx.outputs_data();
+    mutations.extend(
+        (0..outputs_data.len())
+            .filter(|&index| !outputs_data.get(index).expect("data").raw_data().is_empty())
+            .map(Mutation::FlipOutputData),
+    );
+    let outputs = tx.outputs();
+    for index in 1..outputs.len() {
+        // Swapping identical outputs leaves the transaction unchanged.
+        let same_output = outputs.get(index - 1).expect("output").as_slice()
+            == outputs.get(index).expect("output").as_slice();
+        let same_data = outputs_data.get(index - 1).expect("data").as_slice()
+            == outputs_data.get(index).expect("data").as_slice();
+        if !(same_output && same_data) {
+            mutations.push(Mutation::SwapOutputs(index - 1, index));
+        }
+    }
+    if !script.args().raw_data().is_empty() {
+        mutations.push(Mutation::TruncateArgs);
+    }
+    mutations
+}
+
+/// Applies `mutation` to `tx`, returning the mutant and its selected script,
+/// which only changes when the args are truncated.
+fn mutate(
+    tx: &TransactionView,
+    context: &mut Context,
+    script: &Script,
+    mutation: &Mutation,
+) -> (TransactionView, Script) {
+    let builder = tx.as_advanced_builder();
+    let mut inputs: Vec<_> = tx.inputs().into_iter().collect();
+    let mut outputs: Vec<_> = tx.outputs().into_iter().collect();
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
+    let mut mutant_script = script.clone();
+    match *mutation {
+        Mutation::DropWitness(index) => {
+            witnesses.remove(index);
+        }
+        Mutation::DropInput(index) => {
+            inputs.remove(index);
+        }
+        Mutation::FlipOutputData(index) => {
+            let mut data = outputs_data[index].raw_data().to_vec();
+            let last = data.len() - 1;
+            data[last] ^= 0xff;
+            outputs_data[index] = Bytes::from(data).pack();
+        }
+        Mutation::SwapOutputs(a, b) => {
+            outputs.swap(a, b);
+            outputs_data.swap(a, b);
+        }
+        Mutation::TruncateArgs => {
+            let args = script.args().raw_data();
+            mutant_script = script
+                .clone()
+                .as_builder()
+                .args(args.slice(0..args.len() - 1).pack())
+                .build();
+            let replace = |output: &CellOutput| {
+                let mut builder = output.clone().as_builder();
+                if output.lock().as_slice() == script.as_slice() {
+                    builder = builder.lock(mutant_script.clone());
+                }
+                if let Some(type_script) = output.type_().to_opt() {
+                    if type_script.as_slice() == script.as_slice() {
+                        let type_ = ScriptOpt::new_builder()
+                            .set(Some(mutant_script.clone()))
+                            .build();
+                        builder = builder.type_(type_);
+                    }
+                }
+                builder.build()
+            };
+            // Inputs are existing cells, so the cells using the script are
+            // created again with the truncated args.
+            for input in inputs.iter_mut() {
+                let (output, data) = context
+                    .get_cell(&input.previous_output())
+                    .expect("get cell");
+                let mutant_output = replace(&output);
+                if mutant_output.as_slice() != output.as_slice() {
+                    let out_point = context.create_cell(mutant_output, data);
+                    *input = input
+                        .clone()
+                        .as_builder()
+                        .previous_output(out_point)
+                        .build();
+                }
+            }
+            outputs = outputs.iter().map(replace).collect();
+        }
+    }
+    let mutant = builder
+        .set_inputs(inputs)
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .set_witnesses(witnesses)
+        .build();
+    (mutant, mutant_script)
+}
+
+/// Finds `script` in the mutant, keeping the position selected by `setup`
+/// when the script is still there. Returns `None` when no cell uses it.
+fn locate(
+    tx: &TransactionView,
+    context: &Context,
+    script: &Script,
+    setup: &RunningSetup,
+) -> Option<RunningSetup> {
+    let is_script = |candidate: Option<Script>| {
+        candidate
+            .map(|candidate| candidate.as_slice() == script.as_slice())
+            .unwrap_or(false)
+    };
+    let mut positions = Vec::new();
+    for (index, input) in tx.inputs().into_iter().enumerate() {
+        let (output, _) = context
+            .get_cell(&input.previous_output())
+            .expect("get cell");
+        let candidate = if setup.is_lock_script {
+            Some(output.lock())
+        } else {
+            output.type_().to_opt()
+        };
+        if is_script(candidate) {
+            positions.push((false, index));
+        }
+    }
+    if !setup.is_lock_script {
+        for (index, output) in tx.outputs().into_iter().enumerate() {
+            if is_script(output.type_().to_opt()) {
+                positions.push((true, index));
+            }
+        }
+    }
+    let selected = (setup.is_output, setup.script_index);
+    let (is_output, script_index) = if positions.contains(&selected) {
+        selected
+    } else {
+        *positions.first()?
+    };
+    Some(RunningSetup {
+        is_lock_script: setup.is_lock_script,
+        is_output,
+        script_index,
+        native_binaries: setup.native_binaries.clone(),
+    })
+}
+
+/// Verifies every mutant of the passing `tx`, asserting that it fails with the
+/// non-zero exit code `expected` returns for its mutation, and dumps each
+/// mutant for the native simulator as `<test_name>_<mutation>_failure`.
+pub fn check_mutants<F>(
+    test_name: &str,
+    binary_name: &str,
+    tx: &TransactionView,
+    context: &mut Context,
+    setup: &RunningSetup,
+    max_cycles: Cycle,
+    expected: F,
+) where
+    F: Fn(&Mutation) -> i8,
+{
+    let (_, script) = selected_script(tx, context, setup);
+    for mutation in mutations(tx, &script) {
+        let (mutant, mutant_script) = mutate(tx, context, &script, &mutation);
+        let code = expected(&mutation);
+        let name = format!("{}_{}_failure", test_name, mutation);
+        assert_ne!(code, 0, "{}: mutants must be rejected", name);
+        match verify_tx_all_modes(context, &mutant, max_cycles) {
+            Err(err)
+                if err
+                    .to_string()
+                    .contains(&ScriptError::ValidationFailure(code).to_string()) => {}
+            result => panic!("{}: expected exit code {}, got {:?}", name, code, result),
+        }
+        match locate(&mutant, context, &mutant_script, setup) {
+            Some(setup) => {
+                write_native_setup(&name, binary_name, &mutant, context, &setup, code, false)
+            }
+            None => println!("{}: the selected script no longer runs, skip dumping", name),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::differential::{compare_vm_and_native, differential_enabled};
+use crate::sim::{Expectation, Manifest, SimRun};
+use crate::{Loader, TX_FOLDER};
+use ckb_standalone_debugger::transaction::{
+    MockCellDep, MockInfo, MockInput, MockTransaction, ReprMockTransaction,
+};
+use ckb_testtool::context::Context;
+use ckb_tool::ckb_types::{
+    core::{DepType, HeaderView, TransactionView},
+    packed::{Byte32, CellDep, OutPoint, OutPointVec},
+    prelude::*,
+};
+use ckb_x64_simulator::RunningSetup;
+use serde_json::to_string_pretty;
+use std::fs;
+use std::path::PathBuf;
+
+pub fn create_test_folder(name: &str) -> PathBuf {
+    let mut path = TX_FOLDER.clone();
+    path.push(&name);
+    fs::create_dir_all(&path).expect("create folder");
+    path
+}
+
+pub fn build_mock_transaction(tx: &TransactionView, context: &Context) -> MockTransaction {
+    let mock_inputs: Vec<_> = tx
+        .inputs()
+        .into_iter()
+        .map(|input| {
+            let (output, data) = context
+                .get_cell(&input.previous_output())
+                .expect("get cell");
+            let header = block_hash(context, &input.previous_output());
+            MockInput {
+                input,
+                output,
+                data,
+                header,
+            }
+        })
+        .collect();
+    // Member cells of a dep group are added as code deps in place of the
+    // group, matching the cell deps CKB-VM sees. The dep group cells are
+    // appended after them, so the debugger can still resolve the groups.
+    let mut mock_cell_deps = Vec::new();
+    let mut mock_dep_groups = Vec::new();
+    for cell_dep in tx.cell_deps().into_iter() {
+        let (output, data) = context.get_cell(&cell_dep.out_point()).expect("get cell");
+        let header = block_hash(context, &cell_dep.out_point());
+        if cell_dep.dep_type() != DepType::DepGroup.into() {
+            mock_cell_deps.push(MockCellDep {
+                cell_dep,
+                output,
+                data,
+                header,
+            });
+            continue;
+        }
+        for out_point in dep_group_members(&data) {
+            let (output, data) = context.get_cell(&out_point).expect("get dep group member");
+            let header = block_hash(context, &out_point);
+            mock_cell_deps.push(MockCellDep {
+                cell_dep: CellDep::new_builder()
+                    .out_point(out_point)
+                    .dep_type(DepType::Code.into())
+                    .build(),
+                output,
+                data,
+                header,
+            });
+        }
+        mock_dep_groups.push(MockCellDep {
+            cell_dep,
+            output,
+            data,
+            header,
+        });
+    }
+    mock_cell_deps.extend(mock_dep_groups);
+    let header_deps = mock_headers(tx, context, &mock_inputs, &mock_cell_deps);
+    let mock_info = MockInfo {
+        inputs: mock_inputs,
+        cell_deps: mock_cell_deps,
+        header_deps,
+    };
+    MockTransaction {
+        mock_info,
+        tx: tx.data(),
+    }
+}
+
+/// Returns the hash of the block a cell was committed in, as linked with
+/// `Context::link_cell_with_block`.
+fn block_hash(context: &Context, out_point: &OutPoint) -> Option<Byte32> {
+    context
+        .transaction_infos
+        .get(out_point)
+        .map(|info| info.block_hash.clone())
+}
+
+/// Collects the headers of `tx.header_deps()`, followed by the headers of any
+/// committed inputs and cell deps, which the debugger and the simulator look
+/// up from the same list.
+fn mock_headers(
+    tx: &TransactionView,
+    context: &Context,
+    inputs: &[MockInput],
+    cell_deps: &[MockCellDep],
+) -> Vec<HeaderView> {
+    let block_hashes = tx
+        .header_deps()
+        .into_iter()
+        .chain(inputs.iter().filter_map(|input| input.header.clone()))
+        .chain(
+            cell_deps
+                .iter()
+                .filter_map(|cell_dep| cell_dep.header.clone()),
+        );
+    let mut headers: Vec<HeaderView> = Vec::new();
+    for block_hash in block_hashes {
+        if headers.iter().all(|header| header.hash() != block_hash) {
+            let header = context.headers.get(&block_hash).expect("get header");
+            headers.push(header.clone());
+        }
+    }
+    headers
+}
+
+/// Parses the out points listed in the data of a dep group cell.
+pub fn dep_group_members(data: &[u8]) -> Vec<OutPoint> {
+    OutPointVec::from_slice(data)
+        .expect("dep group data")
+        .into_iter()
+        .collect()
+}
+
+pub fn rewrite_setup(setup: &RunningSetup, binary_suffix: &str) -> RunningSetup {
+    let mut setup2 = setup.clone();
+    setup2.native_binaries = setup
+        .native_binaries
+        .iter()
+        .map(|(key, binary)| (key.clone(), format!("{}{}", binary, binary_suffix)))
+        .collect();
+    setup2
+}
+
+pub fn write_native_setup(
+    test_name: &str,
+    binary_name: &str,
+    tx: &TransactionView,
+    context: &Context,
+    setup: &RunningSetup,
+    return_code: i8,
+    enable_sanitizers: bool,
+) {
+    let folder = create_test_folder(test_name);
+    let mock_tx = build_mock_transaction(&tx, &context);
+    let repr_tx: ReprMockTransaction = mock_tx.into();
+    let tx_json = to_string_pretty(&repr_tx).expect("serialize to json");
+    fs::write(folder.join("tx.json"), tx_json).expect("write tx to local file");
+    let setup_json = to_string_pretty(setup).expect("serialize to json");
+    fs::write(folder.join("setup.json"), setup_json).expect("write setup to local file");
+
+    let binary = Loader::default().path(binary_name);
+    let mut runs = vec![SimRun {
+        binary: binary.clone(),
+        tx_file: folder.join("tx.json"),
+        setup_file: folder.join("setup.json"),
+        expected: Expectation::ExitCode(return_code),
+        deny_stderr: false,
+    }];
+
+    if enable_sanitizers {
+        for suffix in &[".ubsan", ".asan"] {
+            let sanitizer_setup = rewrite_setup(setup, suffix);
+            let sanitizer_setup_json =
+                to_string_pretty(&sanitizer_setup).expect("serialize to json");
+            let setup_file = folder.join(format!("{}_setup.json", &suffix[1..]));
+            fs::write(&setup_file, sanitizer_setup_json).expect("write setup to local file");
+
+            let mut sanitizer_binary = binary.clone().into_os_string();
+            sanitizer_binary.push(suffix);
+            runs.push(SimRun {
+                binary: sanitizer_binary.into(),
+                tx_file: folder.join("tx.json"),
+                setup_file,
+                expected: Expectation::ExitCode(return_code),
+                deny_stderr: true,
+            });
+        }
+    }
+
+    let manifest = Manifest {
+        name: test_name.to_string(),
+        runs,
+    };
+    manifest
+        .write(&folder)
+        .expect("write manifest to local file");
+
+    if differential_enabled() {
+        compare_vm_and_native(test_name, tx, context, setup, &manifest.runs[0]);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{
+    check_mutants, random_32bytes, verify_tx_all_modes, write_native_setup, Loader, Mutation,
+};
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::Error;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Cycle, DepType, TransactionBuilder, TransactionView},
+    packed::{Byte32, CellDep, CellInput, CellOutput, OutPoint, OutPointVec, Script, ScriptOpt},
+    prelude::*,
+};
+use ckb_x64_simulator::RunningSetup;
+use std::collections::HashMap;
+
+/// A cell to be created as an input or added as an output of a `TxScenario`.
+#[derive(Clone)]
+pub struct Cell {
+    lock: Script,
+    type_: Option<Script>,
+    capacity: u64,
+    data: Bytes,
+}
+
+impl Cell {
+    pub fn new(lock: &Script, capacity: u64) -> Self {
+        Cell {
+            lock: lock.clone(),
+            type_: None,
+            capacity,
+            data: Bytes::new(),
+        }
+    }
+
+    pub fn type_(mut self, type_: &Script) -> Self {
+        self.type_ = Some(type_.clone());
+        self
+    }
+
+    pub fn data(mut self, data: Bytes) -> Self {
+        self.data = data;
+        self
+    }
+
+    fn output(&self) -> CellOutput {
+        CellOutput::new_builder()
+            .capacity(self.capacity.pack())
+            .lock(self.lock.clone())
+            .type_(ScriptOpt::new_builder().set(self.type_.clone()).build())
+            .build()
+    }
+}
+
+/// Fluent builder for a contract test transaction, which deploys the contracts,
+/// creates the input cells in a `Context` and records the `RunningSetup` used
+/// when dumping the transaction for the native simulator.
+///
+/// ```ignore
+/// let mut scenario = TxScenario::new();
+/// scenario.deploy("nft-validator");
+/// let owner = scenario.lock_script();
+/// let nft_type = scenario.script("nft-validator", governance_args);
+/// scenario
+///     .input(Cell::new(&owner, 1000).type_(&nft_type).data(nft_data.clone()))
+///     .output(Cell::new(&owner, 999).type_(&nft_type).data(nft_data))
+///     .run_input_type(0);
+/// scenario.verify(MAX_CYCLES).expect("pass verification");
+/// scenario.dump("nft_transfer", "nft-validator-sim", 0);
+/// ```
+pub struct TxScenario {
+    context: Context,
+    deployed: HashMap<String, OutPoint>,
+    always_success_out_point: OutPoint,
+    cell_deps: Vec<CellDep>,
+    header_deps: Vec<Byte32>,
+    inputs: Vec<CellInput>,
+    outputs: Vec<Cell>,
+    witnesses: Vec<Bytes>,
+    setup: RunningSetup,
+    enable_sanitizers: bool,
+}
+
+impl Default for TxScenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TxScenario {
+    /// Starts a scenario with the always success lock deployed as a cell dep.
+    pub fn new() -> Self {
+        let mut context = Context::default();
+        let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+        let cell_deps = vec![CellDep::new_builder()
+            .out_point(always_success_out_point.clone())
+            .build()];
+        TxScenario {
+            context,
+            deployed: HashMap::default(),
+            always_success_out_point,
+            cell_deps,
+            header_deps: vec![],
+            inputs: vec![],
+            outputs: vec![],
+            witnesses: vec![],
+            setup: RunningSetup {
+                is_lock_script: false,
+                is_output: false,
+                script_index: 0,
+                native_binaries: HashMap::default(),
+            },
+            enable_sanitizers: false,
+        }
+    }
+
+    /// Deploys the binary `name` from the build folder and adds it as a cell dep.
+    pub fn deploy(&mut self, name: &str) -> &mut Self {
+        let out_point = self
+            .context
+            .deploy_cell(Loader::default().load_binary(name));
+        self.cell_deps
+            .push(CellDep::new_builder().out_point(out_point.clone()).build());
+        self.deployed.insert(name.to_string(), out_point);
+        self
+    }
+
+    /// Returns a new always success lock, unique by its random args.
+    pub fn lock_script(&mut self) -> Script {
+        self.context
+            .build_script(&self.always_success_out_point, random_32bytes())
+            .expect("lock script")
+    }
+
+    /// Returns a script running the deployed binary `name` with `args`.
+    pub fn script(&mut self, name: &str, args: Bytes) -> Script {
+        let out_point = self.deployed.get(name).expect("deployed binary");
+        self.context.build_script(out_point, args).expect("script")
+    }
+
+    /// Creates `cell` in the context and spends it as the next input.
+    pub fn input(&mut self, cell: Cell) -> &mut Self {
+        let out_point = self.context.create_cell(cell.output(), cell.data);
+        self.inputs
+            .push(CellInput::new_builder().previous_output(out_point).build());
+        self
+    }
+
+    pub fn output(&mut self, cell: Cell) -> &mut Self {
+        self.outputs.push(cell);
+        self
+    }
+
+    pub fn cell_dep(&mut self, cell_dep: CellDep) -> &mut Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    /// Replaces the cell deps added so far with a single dep group cell
+    /// listing them.
+    pub fn dep_group(&mut self) -> &mut Self {
+        let out_points = OutPointVec::new_builder()
+            .set(self.cell_deps.iter().map(|dep| dep.out_point()).collect())
+            .build();
+        let out_point = self.context.create_cell(
+            CellOutput::new_builder().capacity(1000u64.pack()).build(),
+            out_points.as_bytes(),
+        );
+        self.cell_deps = vec![CellDep::new_builder()
+            .out_point(out_point)
+            .dep_type(DepType::DepGroup.into())
+            .build()];
+        self
+    }
+
+    pub fn header_dep(&mut self, block_hash: Byte32) -> &mut Self {
+        self.header_deps.push(block_hash);
+        self
+    }
+
+    pub fn witness(&mut self, witness: Bytes) -> &mut Self {
+        self.witnesses.push(witness);
+        self
+    }
+
+    /// Runs the type script of input `index` in the native simulator.
+    pub fn run_input_type(&mut self, index: usize) -> &mut Self {
+        self.run_script(false, false, index)
+    }
+
+    /// Runs the type script of output `index` in the native simulator.
+    pub fn run_output_type(&mut self, index: usize) -> &mut Self {
+        self.run_script(false, true, index)
+    }
+
+    /// Runs the lock script of input `index` in the native simulator.
+    pub fn run_input_lock(&mut self, index: usize) -> &mut Self {
+        self.run_script(true, false, index)
+    }
+
+    fn run_script(&mut self, is_lock_script: bool, is_output: bool, index: usize) -> &mut Self {
+        self.setup.is_lock_script = is_lock_script;
+        self.setup.is_output = is_output;
+        self.setup.script_index = index;
+        self
+    }
+
+    /// Maps a dynamically loaded library to its native build, see `RunningSetup`.
+    pub fn native_binary(&mut self, code_hash: &str, path: &str) -> &mut Self {
+        self.setup
+            .native_binaries
+            .insert(code_hash.to_string(), path.to_string());
+        self
+    }
+
+    /// Also dumps runs of the `.ubsan` and `.asan` builds of the native binary.
+    pub fn with_sanitizers(&mut self) -> &mut Self {
+        self.enable_sanitizers = true;
+        self
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    pub fn inputs(&self) -> &[CellInput] {
+        &self.inputs
+    }
+
+    pub fn setup(&self) -> &RunningSetup {
+        &self.setup
+    }
+
+    pub fn build(&self) -> TransactionView {
+        TransactionBuilder::default()
+            .inputs(self.inputs.clone())
+            .outputs(self.outputs.iter().map(Cell::output))
+            .outputs_data(self.outputs.iter().map(|cell| cell.data.pack()))
+            .cell_deps(self.cell_deps.clone())
+            .header_deps(self.header_deps.clone())
+            .witnesses(self.witnesses.iter().map(|witness| witness.pack()))
+            .build()
+    }
+
+    /// Verifies the transaction with `verify_tx_all_modes`.
+    pub fn verify(&self, max_cycles: Cycle) -> Result<Cycle, Error> {
+        verify_tx_all_modes(&self.context, &self.build(), max_cycles)
+    }
+
+    /// Dumps the transaction for the native simulator, expecting `return_code`.
+    pub fn dump(&self, test_name: &str, binary_name: &str, return_code: i8) {
+        write_native_setup(
+            test_name,
+            binary_name,
+            &self.build(),
+            &self.context,
+            &self.setup,
+            return_code,
+            self.enable_sanitizers,
+        );
+    }
+
+    /// Checks the mutants of the transaction with `check_mutants`.
+    pub fn check_mutants<F>(
+        &mut self,
+        test_name: &str,
+        binary_name: &str,
+        max_cycles: Cycle,
+        expected: F,
+    ) where
+        F: Fn(&Mutation) -> i8,
+    {
+        let tx = self.build();
+        check_mutants(
+            test_name,
+            binary_name,
+            &tx,
+            &mut self.context,
+            &self.setup,
+            max_cycles,
+            expected,
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, to_string_pretty};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Name of the manifest file describing a dumped test case.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Exit status a native simulator run is expected to end with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Expectation {
+    /// The binary exits with exactly this code.
+    ExitCode(i8),
+}
+
+/// A single invocation of a native simulator binary.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SimRun {
+    pub binary: PathBuf,
+    pub tx_file: PathBuf,
+    pub setup_file: PathBuf,
+    pub expected: Expectation,
+    /// Treat any output on stderr as a failure, used by sanitizer builds.
+    pub deny_stderr: bool,
+}
+
+/// A dumped test case, stored as `manifest.json` next to its tx and setup files.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    pub name: String,
+    pub runs: Vec<SimRun>,
+}
+
+impl Manifest {
+    pub fn write(&self, folder: &Path) -> io::Result<()> {
+        let json = to_string_pretty(self).expect("serialize to json");
+        fs::write(folder.join(MANIFEST_FILE), json)
+    }
+}
+
+/// Outcome of a single `SimRun`.
+#[derive(Debug)]
+pub struct RunResult {
+    pub case: String,
+    pub binary: String,
+    pub expected: Expectation,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+    pub duration: Duration,
+    /// Reason the run is considered failed, `None` when it passed.
+    pub failure: Option<String>,
+}
+
+/// Loads the manifests of all cases under `dir`, sorted by case name.
+pub fn discover(dir: &Path) -> io::Result<Vec<Manifest>> {
+    let mut cases = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path().join(MANIFEST_FILE);
+        if !path.is_file() {
+            continue;
+        }
+        let manifest: Manifest = serde_json::from_slice(&fs::read(&path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        cases.push(manifest);
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// Runs every case on `jobs` worker threads, returning results in manifest order.
+pub fn run_all(cases: &[Manifest], jobs: usize) -> Vec<RunResult> {
+    let queue: Vec<(usize, String, SimRun)> = cases
+        .iter()
+        .flat_map(|case| {
+            case.runs
+                .iter()
+                .map(move |run| (case.name.clone(), run.clone()))
+        })
+        .enumerate()
+        .map(|(index, (case, run))| (index, case, run))
+        .collect();
+    let queue = Arc::new(Mutex::new(queue.into_iter()));
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..jobs.max(1))
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                let next = queue.lock().expect("lock queue").next();
+                match next {
+                    Some((index, case, run)) => {
+                        let result = run_one(&case, &run);
+                        sender.send((index, result)).expect("send result");
+                    }
+                    None => break,
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+    let mut results: Vec<(usize, RunResult)> = receiver.iter().collect();
+    for worker in workers {
+        worker.join().expect("join worker");
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+pub(crate) fn run_one(case: &str, run: &SimRun) -> RunResult {
+    let start = Instant::now();
+    let output = Command::new(&run.binary)
+        .env("CKB_TX_FILE", &run.tx_file)
+        .env("CKB_RUNNING_SETUP", &run.setup_file)
+        .output();
+    let duration = start.elapsed();
+    let binary = run.binary.to_string_lossy().into_owned();
+    match output {
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+            let exit_code = output.status.code();
+            let failure = check(run, exit_code, &stderr);
+            RunResult {
+                case: case.to_string(),
+                binary,
+                expected: run.expected,
+                exit_code,
+                stderr,
+                duration,
+                failure,
+            }
+        }
+        Err(err) => RunResult {
+            case: case.to_string(),
+            binary,
+            expected: run.expected,
+            exit_code: None,
+            stderr: String::new(),
+            duration,
+            failure: Some(format!("failed to start binary: {}", err)),
+        },
+    }
+}
+
+fn check(run: &SimRun, exit_code: Option<i32>, stderr: &str) -> Option<String> {
+    // Exit codes are a single byte, scripts return them as i8.
+    let code = match exit_code {
+        Some(code) => code as u8 as i8,
+        None => return Some("terminated by signal".to_string()),
+    };
+    match run.expected {
+        Expectation::ExitCode(expected) if code != expected => Some(format!(
+            "return code {} does not match expected {}",
+            code, expected
+        )),
+        _ if run.deny_stderr && !stderr.is_empty() => Some("errors in stderr".to_string()),
+        _ => None,
+    }
+}
+
+pub fn write_json_report(results: &[RunResult], path: &Path) -> io::Result<()> {
+    let runs: Vec<_> = results
+        .iter()
+        .map(|result| {
+            json!({
+                "case": result.case,
+                "binary": result.binary,
+                "expected": result.expected,
+                "exit_code": result.exit_code,
+                "duration_ms": result.duration.as_millis() as u64,
+                "passed": result.failure.is_none(),
+                "failure": result.failure,
+                "stderr": result.stderr,
+            })
+        })
+        .collect();
+    let report = json!({
+        "tests": results.len(),
+        "failures": results.iter().filter(|r| r.failure.is_some()).count(),
+        "runs": runs,
+    });
+    fs::write(path, to_string_pretty(&report).expect("serialize to json"))
+}
+
+pub fn write_junit_report(results: &[RunResult], path: &Path) -> io::Result<()> {
+    let failures = results.iter().filter(|r| r.failure.is_some()).count();
+    let total: Duration = results.iter().map(|r| r.duration).sum();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuite name=\"native-simulator\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failures,
+        total.as_secs_f64()
+    ));
+    for result in results {
+        let binary = Path::new(&result.binary)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| result.binary.clone());
+        xml.push_str(&format!(
+            "  <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            escape_xml(&result.case),
+            escape_xml(&binary),
+            result.duration.as_secs_f64()
+        ));
+        match &result.failure {
+            Some(failure) => xml.push_str(&format!(
+                ">\n    <failure message=\"{}\">{}</failure>\n  </testcase>\n",
+                escape_xml(failure),
+                escape_xml(&result.stderr)
+            )),
+            None => xml.push_str("/>\n"),
+        }
+    }
+    xml.push_str("</testsuite>\n");
+    fs::write(path, xml)
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}